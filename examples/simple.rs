@@ -76,9 +76,18 @@ fn compare_items() {
     for span in differ.spans() {
         match span.tag {
             Tag::Equal => (), // ignore
-            Tag::Insert => println!("{:?}", span),
-            Tag::Delete => println!("{:?}", span),
-            Tag::Replace => println!("{:?}", span),
+            Tag::Insert => print_items('+', &b[span.b_start..span.b_end]),
+            Tag::Delete => print_items('-', &a[span.a_start..span.a_end]),
+            Tag::Replace => {
+                print_items('-', &a[span.a_start..span.a_end]);
+                print_items('+', &b[span.b_start..span.b_end]);
+            }
         }
     }
 }
+
+fn print_items(c: char, items: &[Item]) {
+    for item in items {
+        println!("{} {} at ({}, {})", c, item.text, item.x, item.y);
+    }
+}
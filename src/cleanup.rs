@@ -0,0 +1,579 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::structs::{Span, Tag};
+use crate::transform::push_run;
+
+// The cleanups work on a diff-match-patch-style list of edits: each is an
+// equal, deleted, or inserted run of items (a `Tag::Replace` span becomes
+// a deletion followed by an insertion). Spans are only positions, so the
+// edits are turned back into spans by walking their lengths.
+type Edits<'a, T> = Vec<(Tag, Vec<&'a T>)>;
+
+/// Returns the spans that convert `a` into `b` rewritten to be fewer and
+/// more meaningful for a human reader, given the spans that convert `a`
+/// into `b` (e.g., as returned by
+/// [`spans()`](struct.Differ.html#method.spans)).
+///
+/// This is diff-match-patch's semantic cleanup: an equal run that is no
+/// longer than the changes on both sides of it is turned into a deletion
+/// and an insertion and merged with its neighbours, and a deletion and
+/// insertion that overlap by at least half of either are split around
+/// the overlap. For characters or bytes follow this with
+/// [`cleanup_semantic_lossless()`](fn.cleanup_semantic_lossless.html).
+///
+/// ```
+/// use differ::{cleanup_semantic, Differ, Span};
+///
+/// let a = b"mouse";
+/// let b = b"sofas";
+/// let spans = Differ::new(&a[..], &b[..]).spans();
+/// assert_eq!(spans.len(), 5); // "o" and "s" are equal
+/// assert_eq!(
+///     cleanup_semantic(&a[..], &b[..], &spans),
+///     vec![Span::replace(0, 5, 0, 5)]
+/// );
+/// ```
+pub fn cleanup_semantic<T>(a: &[T], b: &[T], spans: &[Span]) -> Vec<Span>
+where
+    T: PartialEq,
+{
+    let mut edits = edits_for_spans(a, b, spans);
+    merge(&mut edits);
+    eliminate_equalities(&mut edits);
+    split_overlaps(&mut edits);
+    spans_for_edits(&edits)
+}
+
+/// Returns the spans that convert `a` into `b` with each change that is
+/// surrounded by equal runs shifted sideways to line up with the nearest
+/// line, word, or punctuation boundary, given the spans that convert `a`
+/// into `b`.
+///
+/// This is diff-match-patch's semantic lossless cleanup: no span is
+/// removed, only moved to where a human would expect it.
+///
+/// ```
+/// use differ::{cleanup_semantic_lossless, Differ, Span};
+///
+/// let a = b"private Thread currentThread;";
+/// let b = b"private volatile Thread currentThread;";
+/// let spans = Differ::new(&a[..], &b[..]).spans();
+/// assert_eq!(spans[1], Span::insert(6, 6, 6, 15)); // "e volatil"
+/// assert_eq!(
+///     cleanup_semantic_lossless(&a[..], &b[..], &spans),
+///     vec![
+///         Span::equal(0, 8, 0, 8),    // "private "
+///         Span::insert(8, 8, 8, 17),  // "volatile "
+///         Span::equal(8, 29, 17, 38), // "Thread currentThread;"
+///     ]
+/// );
+/// ```
+pub fn cleanup_semantic_lossless<T>(
+    a: &[T],
+    b: &[T],
+    spans: &[Span],
+) -> Vec<Span>
+where
+    T: Copy + Into<char> + PartialEq,
+{
+    let mut edits = edits_for_spans(a, b, spans);
+    merge(&mut edits);
+    shift_to_boundaries(&mut edits);
+    spans_for_edits(&edits)
+}
+
+/// Returns the spans that convert `a` into `b` with short equal runs
+/// between changes folded into the changes, given the spans that convert
+/// `a` into `b` and the `edit_cost`, i.e., the number of items that an
+/// extra change costs a reader.
+///
+/// This is diff-match-patch's efficiency cleanup: an equal run shorter
+/// than `edit_cost` that has a deletion and an insertion on both sides,
+/// or shorter than half of `edit_cost` with changes on three of those
+/// four sides, is turned into a deletion and an insertion. diff-match-
+/// patch uses an `edit_cost` of `4`.
+///
+/// ```
+/// use differ::{cleanup_efficiency, Differ, Span};
+///
+/// let a = b"abwxyzcd";
+/// let b = b"12wxyz34";
+/// let spans = Differ::new(&a[..], &b[..]).spans();
+/// assert_eq!(spans.len(), 3);
+/// assert_eq!(cleanup_efficiency(&a[..], &b[..], &spans, 4), spans);
+/// assert_eq!(
+///     cleanup_efficiency(&a[..], &b[..], &spans, 5),
+///     vec![Span::replace(0, 8, 0, 8)]
+/// );
+/// ```
+pub fn cleanup_efficiency<T>(
+    a: &[T],
+    b: &[T],
+    spans: &[Span],
+    edit_cost: usize,
+) -> Vec<Span>
+where
+    T: PartialEq,
+{
+    let mut edits = edits_for_spans(a, b, spans);
+    merge(&mut edits);
+    let mut changed = false;
+    let mut equalities: Vec<usize> = vec![];
+    let mut last_equality: Option<usize> = None; // length
+    let (mut pre_insert, mut pre_delete) = (false, false);
+    let (mut post_insert, mut post_delete) = (false, false);
+    let mut i = 0;
+    while i < edits.len() {
+        if edits[i].0 == Tag::Equal {
+            if edits[i].1.len() < edit_cost
+                && (post_insert || post_delete)
+            {
+                equalities.push(i);
+                pre_insert = post_insert;
+                pre_delete = post_delete;
+                last_equality = Some(edits[i].1.len());
+            } else {
+                equalities.clear();
+                last_equality = None;
+            }
+            post_insert = false;
+            post_delete = false;
+        } else {
+            if edits[i].0 == Tag::Delete {
+                post_delete = true;
+            } else {
+                post_insert = true;
+            }
+            let sides =
+                [pre_insert, pre_delete, post_insert, post_delete]
+                    .iter()
+                    .filter(|&&side| side)
+                    .count();
+            if matches!(last_equality, Some(len)
+                if sides == 4 || (len * 2 < edit_cost && sides == 3))
+            {
+                let j = equalities.pop().unwrap();
+                unequal(&mut edits, j);
+                last_equality = None;
+                changed = true;
+                if pre_insert && pre_delete {
+                    post_insert = true;
+                    post_delete = true;
+                    equalities.clear();
+                } else {
+                    equalities.pop();
+                    post_insert = false;
+                    post_delete = false;
+                    // Rescan from after the previous equality
+                    i = equalities.last().map_or(0, |&j| j + 1);
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    if changed {
+        merge(&mut edits);
+    }
+    spans_for_edits(&edits)
+}
+
+fn edits_for_spans<'a, T>(
+    a: &'a [T],
+    b: &'a [T],
+    spans: &[Span],
+) -> Edits<'a, T> {
+    let mut edits = vec![];
+    for span in spans {
+        let deleted: Vec<_> =
+            a[span.a_start..span.a_end].iter().collect();
+        if span.tag == Tag::Equal {
+            edits.push((Tag::Equal, deleted));
+            continue;
+        }
+        edits.push((Tag::Delete, deleted));
+        edits.push((
+            Tag::Insert,
+            b[span.b_start..span.b_end].iter().collect(),
+        ));
+    }
+    edits.retain(|(_, items)| !items.is_empty());
+    edits
+}
+
+fn spans_for_edits<T>(edits: &Edits<T>) -> Vec<Span> {
+    let mut spans = vec![];
+    for (tag, items) in edits {
+        if items.is_empty() {
+            continue;
+        }
+        match tag {
+            Tag::Equal => {
+                push_run(&mut spans, true, items.len(), items.len())
+            }
+            Tag::Delete => push_run(&mut spans, false, items.len(), 0),
+            _ => push_run(&mut spans, false, 0, items.len()),
+        }
+    }
+    spans
+}
+
+// Turns the equality at index i into a deletion followed by an insertion.
+fn unequal<T>(edits: &mut Edits<T>, i: usize) {
+    let items = edits[i].1.clone();
+    edits.insert(i, (Tag::Delete, items));
+    edits[i + 1].0 = Tag::Insert;
+}
+
+// Eliminates each equality that is no longer than the largest deletion or
+// insertion on both sides of it.
+fn eliminate_equalities<T: PartialEq>(edits: &mut Edits<T>) {
+    let mut changed = false;
+    let mut equalities: Vec<usize> = vec![];
+    let mut last_equality: Option<usize> = None; // length
+    let (mut inserted1, mut deleted1) = (0, 0); // before the equality
+    let (mut inserted2, mut deleted2) = (0, 0); // after the equality
+    let mut i = 0;
+    while i < edits.len() {
+        let len = edits[i].1.len();
+        if edits[i].0 == Tag::Equal {
+            equalities.push(i);
+            inserted1 = inserted2;
+            deleted1 = deleted2;
+            inserted2 = 0;
+            deleted2 = 0;
+            last_equality = Some(len);
+        } else {
+            if edits[i].0 == Tag::Insert {
+                inserted2 += len;
+            } else {
+                deleted2 += len;
+            }
+            if matches!(last_equality, Some(len)
+                if len <= inserted1.max(deleted1)
+                    && len <= inserted2.max(deleted2))
+            {
+                let j = equalities.pop().unwrap();
+                unequal(edits, j);
+                equalities.pop(); // Its lengths are now out of date
+                inserted1 = 0;
+                deleted1 = 0;
+                inserted2 = 0;
+                deleted2 = 0;
+                last_equality = None;
+                changed = true;
+                // Rescan from the previous equality (or the start)
+                i = equalities.last().map_or(0, |&j| j);
+                equalities.pop();
+                continue;
+            }
+        }
+        i += 1;
+    }
+    if changed {
+        merge(edits);
+    }
+}
+
+// Splits each deletion followed by an insertion that overlap (the end of
+// one is the start of the other) by at least half of either into a
+// change, an equality, and a change.
+fn split_overlaps<T: PartialEq>(edits: &mut Edits<T>) {
+    let mut i = 1;
+    while i < edits.len() {
+        if edits[i - 1].0 == Tag::Delete && edits[i].0 == Tag::Insert {
+            let deleted = edits[i - 1].1.clone();
+            let inserted = edits[i].1.clone();
+            let overlap1 = overlap(&deleted, &inserted);
+            let overlap2 = overlap(&inserted, &deleted);
+            let long_enough = |n: usize| {
+                n > 0
+                    && (n * 2 >= deleted.len() || n * 2 >= inserted.len())
+            };
+            if overlap1 >= overlap2 {
+                if long_enough(overlap1) {
+                    edits.insert(
+                        i,
+                        (Tag::Equal, inserted[..overlap1].to_vec()),
+                    );
+                    edits[i - 1].1 =
+                        deleted[..deleted.len() - overlap1].to_vec();
+                    edits[i + 1].1 = inserted[overlap1..].to_vec();
+                    i += 1;
+                }
+            } else if long_enough(overlap2) {
+                edits.insert(
+                    i,
+                    (Tag::Equal, deleted[..overlap2].to_vec()),
+                );
+                edits[i - 1] = (
+                    Tag::Insert,
+                    inserted[..inserted.len() - overlap2].to_vec(),
+                );
+                edits[i + 1] =
+                    (Tag::Delete, deleted[overlap2..].to_vec());
+                i += 1;
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    edits.retain(|(_, items)| !items.is_empty());
+}
+
+// Shifts each change that is surrounded by equalities to the position
+// with the best boundary score.
+fn shift_to_boundaries<T>(edits: &mut Edits<T>)
+where
+    T: Copy + Into<char> + PartialEq,
+{
+    let mut i = 1;
+    while i + 1 < edits.len() {
+        if edits[i - 1].0 == Tag::Equal && edits[i + 1].0 == Tag::Equal {
+            let mut equality1 = edits[i - 1].1.clone();
+            let mut edit = edits[i].1.clone();
+            let mut equality2 = edits[i + 1].1.clone();
+            // Shift the change as far left as possible
+            let common = suffix(&equality1, &edit);
+            if common > 0 {
+                let moved = edit.split_off(edit.len() - common);
+                equality1.truncate(equality1.len() - common);
+                edit.splice(0..0, moved.iter().copied());
+                equality2.splice(0..0, moved);
+            }
+            // Then step right one item at a time, keeping the best score
+            let mut best = (
+                equality1.clone(),
+                edit.clone(),
+                equality2.clone(),
+                score(&equality1, &edit) + score(&edit, &equality2),
+            );
+            while !edit.is_empty()
+                && !equality2.is_empty()
+                && edit[0] == equality2[0]
+            {
+                equality1.push(edit.remove(0));
+                edit.push(equality2.remove(0));
+                let value =
+                    score(&equality1, &edit) + score(&edit, &equality2);
+                if value >= best.3 {
+                    best = (
+                        equality1.clone(),
+                        edit.clone(),
+                        equality2.clone(),
+                        value,
+                    );
+                }
+            }
+            let (equality1, edit, equality2, _) = best;
+            if edits[i - 1].1 != equality1 {
+                edits[i].1 = edit;
+                if equality2.is_empty() {
+                    edits.remove(i + 1);
+                } else {
+                    edits[i + 1].1 = equality2;
+                }
+                if equality1.is_empty() {
+                    edits.remove(i - 1);
+                    i -= 1;
+                } else {
+                    edits[i - 1].1 = equality1;
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+// Scores the boundary between the end of one and the start of two from 6
+// (an end of the text) down to 0 (inside a word).
+fn score<T>(one: &[&T], two: &[&T]) -> usize
+where
+    T: Copy + Into<char>,
+{
+    let (Some(&&c1), Some(&&c2)) = (one.last(), two.first()) else {
+        return 6;
+    };
+    let (c1, c2): (char, char) = (c1.into(), c2.into());
+    let punctuation1 = !c1.is_alphanumeric();
+    let punctuation2 = !c2.is_alphanumeric();
+    let whitespace1 = punctuation1 && c1.is_whitespace();
+    let whitespace2 = punctuation2 && c2.is_whitespace();
+    let line_break1 = whitespace1 && (c1 == '\n' || c1 == '\r');
+    let line_break2 = whitespace2 && (c2 == '\n' || c2 == '\r');
+    let blank_line1 = line_break1 && ends_with_blank_line(one);
+    let blank_line2 = line_break2 && starts_with_blank_line(two);
+    if blank_line1 || blank_line2 {
+        5
+    } else if line_break1 || line_break2 {
+        4
+    } else if punctuation1 && !whitespace1 && whitespace2 {
+        3 // The end of a sentence
+    } else if whitespace1 || whitespace2 {
+        2
+    } else if punctuation1 || punctuation2 {
+        1
+    } else {
+        0
+    }
+}
+
+// Returns true if the items end with "\n\n" or "\n\r\n".
+fn ends_with_blank_line<T: Copy + Into<char>>(items: &[&T]) -> bool {
+    let chars: Vec<char> =
+        items.iter().rev().take(3).map(|&&c| c.into()).collect();
+    matches!(chars[..], ['\n', '\n', ..] | ['\n', '\r', '\n'])
+}
+
+// Returns true if the items start with "\n\n", "\n\r\n", "\r\n\n", or
+// "\r\n\r\n".
+fn starts_with_blank_line<T: Copy + Into<char>>(items: &[&T]) -> bool {
+    let chars: Vec<char> =
+        items.iter().take(4).map(|&&c| c.into()).collect();
+    let rest = match chars[..] {
+        ['\r', '\n', ..] => &chars[2..],
+        ['\n', ..] => &chars[1..],
+        _ => return false,
+    };
+    matches!(rest, ['\n', ..] | ['\r', '\n', ..])
+}
+
+// Normalizes the edits: merges adjacent runs of the same kind, factors
+// items common to the start or end of a deletion and insertion out into
+// the neighbouring equalities, puts deletions before insertions, and
+// shifts each change surrounded by equalities over one of them where
+// that eliminates it.
+fn merge<T: PartialEq>(edits: &mut Edits<T>) {
+    loop {
+        merge_runs(edits);
+        if !shift_over_equalities(edits) {
+            break;
+        }
+    }
+}
+
+fn merge_runs<T: PartialEq>(edits: &mut Edits<T>) {
+    edits.push((Tag::Equal, vec![])); // Sentinel
+    let mut i = 0;
+    let mut count = 0; // of the changes since the last equality
+    let mut deleted = vec![];
+    let mut inserted = vec![];
+    while i < edits.len() {
+        match edits[i].0 {
+            Tag::Equal => {
+                if count > 1 {
+                    let start = i - count;
+                    let mut common = prefix(&deleted, &inserted);
+                    let mut i_start = start;
+                    if common > 0 {
+                        let items = inserted.drain(..common).collect();
+                        deleted.drain(..common);
+                        if start > 0 && edits[start - 1].0 == Tag::Equal {
+                            edits[start - 1].1.extend(items);
+                        } else {
+                            edits.insert(0, (Tag::Equal, items));
+                            i += 1;
+                            i_start += 1;
+                        }
+                    }
+                    common = suffix(&deleted, &inserted);
+                    if common > 0 {
+                        let items =
+                            inserted.split_off(inserted.len() - common);
+                        deleted.truncate(deleted.len() - common);
+                        edits[i].1.splice(0..0, items);
+                    }
+                    let mut runs = vec![];
+                    if !deleted.is_empty() {
+                        runs.push((
+                            Tag::Delete,
+                            std::mem::take(&mut deleted),
+                        ));
+                    }
+                    if !inserted.is_empty() {
+                        runs.push((
+                            Tag::Insert,
+                            std::mem::take(&mut inserted),
+                        ));
+                    }
+                    i = i_start + runs.len() + 1;
+                    edits.splice(i_start..i_start + count, runs);
+                } else if i > 0
+                    && count == 0
+                    && edits[i - 1].0 == Tag::Equal
+                {
+                    let items = edits.remove(i).1;
+                    edits[i - 1].1.extend(items);
+                } else {
+                    i += 1;
+                }
+                count = 0;
+                deleted.clear();
+                inserted.clear();
+            }
+            Tag::Delete => {
+                count += 1;
+                deleted.extend(edits[i].1.iter().copied());
+                i += 1;
+            }
+            _ => {
+                count += 1;
+                inserted.extend(edits[i].1.iter().copied());
+                i += 1;
+            }
+        }
+    }
+    edits.retain(|(_, items)| !items.is_empty());
+}
+
+// Shifts each change surrounded by equalities over the equality before
+// or after it if the change ends or starts with that equality, e.g.,
+// A<ins>BA</ins>C becomes <ins>AB</ins>AC. Returns true if any were
+// shifted.
+fn shift_over_equalities<T: PartialEq>(edits: &mut Edits<T>) -> bool {
+    let mut changed = false;
+    let mut i = 1;
+    while i + 1 < edits.len() {
+        if edits[i - 1].0 == Tag::Equal && edits[i + 1].0 == Tag::Equal {
+            let before = edits[i - 1].1.len();
+            let after = edits[i + 1].1.len();
+            let len = edits[i].1.len();
+            if len >= before
+                && edits[i].1[len - before..] == edits[i - 1].1[..]
+            {
+                let equality = edits.remove(i - 1).1;
+                i -= 1;
+                edits[i].1.truncate(len - before);
+                edits[i].1.splice(0..0, equality.iter().copied());
+                edits[i + 1].1.splice(0..0, equality);
+                changed = true;
+            } else if len >= after
+                && edits[i].1[..after] == edits[i + 1].1[..]
+            {
+                let equality = edits.remove(i + 1).1;
+                edits[i].1.drain(..after);
+                edits[i].1.extend(equality.iter().copied());
+                edits[i - 1].1.extend(equality);
+                changed = true;
+            }
+        }
+        i += 1;
+    }
+    changed
+}
+
+fn prefix<T: PartialEq>(a: &[&T], b: &[&T]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn suffix<T: PartialEq>(a: &[&T], b: &[&T]) -> usize {
+    a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count()
+}
+
+// Returns the length of the longest end of a that is also a start of b.
+fn overlap<T: PartialEq>(a: &[&T], b: &[&T]) -> usize {
+    (1..=a.len().min(b.len()))
+        .rev()
+        .find(|&n| a[a.len() - n..] == b[..n])
+        .unwrap_or(0)
+}
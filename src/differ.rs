@@ -1,9 +1,16 @@
 // Copyright © 2019 Qtrac Ltd. All rights reserved.
 
-use crate::structs::{Match, Span, Tag};
+use crate::gnu;
+use crate::histogram;
+use crate::myers;
+use crate::patience;
+use crate::structs::{Algorithm, Hunk, Match, Span, Tag};
 use fnv::{FnvHashMap, FnvHashSet};
+use std::cmp::Ordering;
 use std::hash::Hash;
 
+type JunkFn<'a, T> = Box<dyn Fn(&T) -> bool + 'a>;
+
 /// Provides methods for comparing two sequences.
 ///
 /// See the [crate docs](index.html) for an overview and examples.
@@ -14,6 +21,13 @@ where
     a: &'a [T],
     b: &'a [T],
     b2j: FnvHashMap<&'a T, Vec<usize>>,
+    b_popular: FnvHashSet<&'a T>,
+    b_junk: FnvHashSet<&'a T>,
+    is_junk: Option<JunkFn<'a, T>>,
+    algorithm: Algorithm,
+    autojunk: bool,
+    autojunk_min_len: usize,
+    autojunk_percent: f64,
 }
 
 impl<'a, T> Differ<'a, T>
@@ -34,27 +48,163 @@ where
     /// [`matches()`](struct.Differ.html#method.matches), and then use
     /// [`spans_for_matches()`](spans_for_matches.v.html).
     pub fn new(a: &'a [T], b: &'a [T]) -> Self {
-        let mut differ = Differ { a, b, b2j: FnvHashMap::default() };
+        let mut differ = Differ {
+            a,
+            b,
+            b2j: FnvHashMap::default(),
+            b_popular: FnvHashSet::default(),
+            b_junk: FnvHashSet::default(),
+            is_junk: None,
+            algorithm: Algorithm::default(),
+            autojunk: true,
+            autojunk_min_len: 200,
+            autojunk_percent: 1.0,
+        };
         differ.chain_b_seq();
         differ
     }
 
+    /// Sets the [`Algorithm`](enum.Algorithm.html) used to find the
+    /// matches (and hence the spans); the default is
+    /// `Algorithm::SequenceMatcher`.
+    ///
+    /// ```
+    /// use differ::{Algorithm, Differ};
+    ///
+    /// let a = "abcabba".chars().collect::<Vec<_>>();
+    /// let b = "cbabac".chars().collect::<Vec<_>>();
+    /// let differ = Differ::new(&a, &b).algorithm(Algorithm::Myers);
+    /// let same: usize = differ.matches().iter().map(|m| m.length).sum();
+    /// assert_eq!(same, 4); // the longest common subsequence
+    /// ```
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Replaces sequence `a` with the given sequence; this is the
+    /// equivalent of difflib's `set_seq1()`.
+    ///
+    /// The comparison data is computed from sequence `b`, so it is
+    /// cheaper to compare many sequences against the same sequence by
+    /// changing `a` than by creating a new `Differ` for each one.
+    pub fn set_a(&mut self, a: &'a [T]) {
+        self.a = a;
+    }
+
+    /// Sets whether the "popular" element heuristic is used (the default
+    /// is `true`); this is the equivalent of difflib's `autojunk`.
+    ///
+    /// When on, and `b` has at least 200 items, every item that occurs
+    /// more than (1% of `b`'s length) + 1 times is ignored as a match
+    /// anchor (although it may still extend a match). This makes
+    /// comparisons much faster, but can make the diffs of sequences with
+    /// many repeated items (e.g., log files) far from minimal.
+    ///
+    /// This only affects the `Algorithm::SequenceMatcher` algorithm. See
+    /// also
+    /// [`autojunk_threshold()`](struct.Differ.html#method.autojunk_threshold)
+    /// and [`popular()`](struct.Differ.html#method.popular).
+    pub fn autojunk(mut self, autojunk: bool) -> Self {
+        self.autojunk = autojunk;
+        self.chain_b_seq();
+        self
+    }
+
+    /// Turns on the "popular" element heuristic (see
+    /// [`autojunk()`](struct.Differ.html#method.autojunk)) and sets its
+    /// thresholds: the heuristic is only applied when `b` has at least
+    /// `min_len` items, and then ignores every item that occurs more than
+    /// (`percent`% of `b`'s length) + 1 times. The defaults are `200` and
+    /// `1.0`.
+    pub fn autojunk_threshold(
+        mut self,
+        min_len: usize,
+        percent: f64,
+    ) -> Self {
+        self.autojunk = true;
+        self.autojunk_min_len = min_len;
+        self.autojunk_percent = percent;
+        self.chain_b_seq();
+        self
+    }
+
+    /// Returns the items in `b` that were treated as popular by the
+    /// autojunk heuristic (and so aren't used as match anchors) in no
+    /// particular order; this is the equivalent of difflib's `bpopular`.
+    ///
+    /// ```
+    /// use differ::Differ;
+    ///
+    /// let a = vec!["x"; 10];
+    /// let mut b = vec!["x"; 200];
+    /// b.push("y");
+    /// assert_eq!(Differ::new(&a, &b).popular(), vec![&"x"]);
+    /// assert!(Differ::new(&a, &b).autojunk(false).popular().is_empty());
+    /// ```
+    pub fn popular(&self) -> Vec<&'a T> {
+        self.b_popular.iter().copied().collect()
+    }
+
+    /// Sets a predicate that identifies "junk" items; this is the
+    /// equivalent of difflib's `isjunk`.
+    ///
+    /// Items in `b` for which `is_junk` returns `true` are never used to
+    /// anchor a match, but a match found without them is extended to
+    /// include any adjacent equal junk items. For example, treating
+    /// blank lines or whitespace characters as junk stops them from
+    /// pulling unrelated parts of the sequences together.
+    ///
+    /// This only affects the `Algorithm::SequenceMatcher` algorithm.
+    ///
+    /// ```
+    /// use differ::Differ;
+    ///
+    /// let a = " abcd".chars().collect::<Vec<_>>();
+    /// let b = "abcd abcd".chars().collect::<Vec<_>>();
+    /// let differ = Differ::new(&a, &b);
+    /// assert_eq!(differ.matches()[0].a_start, 0); // " abcd"
+    /// let differ = differ.junk(|c| *c == ' ');
+    /// assert_eq!(differ.matches()[0].a_start, 1); // "abcd"
+    /// ```
+    pub fn junk<F>(mut self, is_junk: F) -> Self
+    where
+        F: Fn(&T) -> bool + 'a,
+    {
+        self.is_junk = Some(Box::new(is_junk));
+        self.chain_b_seq();
+        self
+    }
+
     fn chain_b_seq(&mut self) {
+        self.b2j.clear();
+        self.b_popular.clear();
+        self.b_junk.clear();
         for i in 0..self.b.len() {
-            let indexes =
-                self.b2j.entry(&self.b[i]).or_insert_with(Vec::new);
+            let indexes = self.b2j.entry(&self.b[i]).or_default();
             indexes.push(i);
         }
-        let len = self.b.len(); // changed by above
-        if len >= 200 {
-            let mut b_popular = FnvHashSet::default();
-            let test_len = (len as f64 / 100.0).floor() as usize + 1;
+        if let Some(is_junk) = &self.is_junk {
+            for element in self.b2j.keys() {
+                if is_junk(element) {
+                    self.b_junk.insert(*element);
+                }
+            }
+            for element in &self.b_junk {
+                self.b2j.remove(*element);
+            }
+        }
+        let len = self.b.len();
+        if self.autojunk && len >= self.autojunk_min_len {
+            let test_len = (len as f64 * self.autojunk_percent / 100.0)
+                .floor() as usize
+                + 1;
             for (element, indexes) in self.b2j.iter() {
                 if indexes.len() > test_len {
-                    b_popular.insert(*element);
+                    self.b_popular.insert(*element);
                 }
             }
-            for element in &b_popular {
+            for element in &self.b_popular {
                 self.b2j.remove(*element);
             }
         }
@@ -71,6 +221,32 @@ where
         spans_for_matches(&matches)
     }
 
+    /// Returns the spans grouped into [`Hunk`](struct.Hunk.html)s, each
+    /// with up to `context` equal items before and after its changes;
+    /// this is the equivalent of difflib's `get_grouped_opcodes()`.
+    ///
+    /// Equal runs longer than `2 * context` separate hunks, and if the
+    /// sequences are the same there are no hunks. See
+    /// [`hunks_for_spans()`](fn.hunks_for_spans.html) if you already
+    /// have the spans.
+    ///
+    /// ```
+    /// use differ::{Differ, Span};
+    ///
+    /// let a = (1..=20).collect::<Vec<_>>();
+    /// let mut b = a.clone();
+    /// b[1] = 0;
+    /// b[17] = 0;
+    /// let hunks = Differ::new(&a, &b).grouped_spans(3);
+    /// assert_eq!(hunks.len(), 2);
+    /// assert_eq!((hunks[0].a_start, hunks[0].a_end), (0, 5));
+    /// assert_eq!(hunks[0].spans[1], Span::replace(1, 2, 1, 2));
+    /// assert_eq!((hunks[1].a_start, hunks[1].a_end), (14, 20));
+    /// ```
+    pub fn grouped_spans(&self, context: usize) -> Vec<Hunk> {
+        hunks_for_spans(&self.spans(), context)
+    }
+
     /// Returns every [`Match`](struct.Match.html) between the two
     /// sequences.
     ///
@@ -80,26 +256,12 @@ where
     /// necessary to convert sequence `a` into `b`, use
     /// [`spans()`](struct.Differ.html#method.spans).
     pub fn matches(&self) -> Vec<Match> {
-        let a_len = self.a.len();
-        let b_len = self.b.len();
-        let mut queue = vec![(0, a_len, 0, b_len)];
-        let mut matches = vec![];
-        while !queue.is_empty() {
-            let (a_start, a_end, b_start, b_end) = queue.pop().unwrap();
-            let m = self.longest_match(a_start, a_end, b_start, b_end);
-            let i = m.a_start;
-            let j = m.b_start;
-            let k = m.length;
-            if k > 0 {
-                matches.push(m);
-                if a_start < i && b_start < j {
-                    queue.push((a_start, i, b_start, j));
-                }
-                if i + k < a_end && j + k < b_end {
-                    queue.push((i + k, a_end, j + k, b_end));
-                }
-            }
-        }
+        let mut matches = match self.algorithm {
+            Algorithm::SequenceMatcher => self.sequence_matcher_matches(),
+            Algorithm::Myers => myers::matches(self.a, self.b),
+            Algorithm::Patience => patience::matches(self.a, self.b),
+            Algorithm::Histogram => histogram::matches(self.a, self.b),
+        };
         matches.sort();
         let mut a_start = 0;
         let mut b_start = 0;
@@ -123,10 +285,91 @@ where
         if length != 0 {
             non_adjacent.push(Match::new(a_start, b_start, length));
         }
-        non_adjacent.push(Match::new(a_len, b_len, 0));
+        non_adjacent.push(Match::new(self.a.len(), self.b.len(), 0));
         non_adjacent
     }
 
+    fn sequence_matcher_matches(&self) -> Vec<Match> {
+        let mut queue = vec![(0, self.a.len(), 0, self.b.len())];
+        let mut matches = vec![];
+        while let Some((a_start, a_end, b_start, b_end)) = queue.pop() {
+            let m = self.longest_match(a_start, a_end, b_start, b_end);
+            let i = m.a_start;
+            let j = m.b_start;
+            let k = m.length;
+            if k > 0 {
+                matches.push(m);
+                if a_start < i && b_start < j {
+                    queue.push((a_start, i, b_start, j));
+                }
+                if i + k < a_end && j + k < b_end {
+                    queue.push((i + k, a_end, j + k, b_end));
+                }
+            }
+        }
+        matches
+    }
+
+    /// Returns a measure of the sequences' similarity in the range
+    /// `0.0..=1.0`, where `1.0` means identical; this is the equivalent
+    /// of difflib's `ratio()`.
+    ///
+    /// The ratio is `2.0 * M / T` where `M` is the number of matched
+    /// items and `T` is the total number of items in both sequences (two
+    /// empty sequences have a ratio of `1.0`).
+    ///
+    /// This computes the [`matches()`](struct.Differ.html#method.matches)
+    /// so is expensive;
+    /// [`quick_ratio()`](struct.Differ.html#method.quick_ratio) and
+    /// [`real_quick_ratio()`](struct.Differ.html#method.real_quick_ratio)
+    /// are upper bounds that are much cheaper to compute.
+    ///
+    /// ```
+    /// use differ::Differ;
+    ///
+    /// let a = "abcd".chars().collect::<Vec<_>>();
+    /// let b = "bcde".chars().collect::<Vec<_>>();
+    /// let differ = Differ::new(&a, &b);
+    /// assert_eq!(differ.ratio(), 0.75);
+    /// ```
+    pub fn ratio(&self) -> f64 {
+        let matched = self.matches().iter().map(|m| m.length).sum();
+        calculate_ratio(matched, self.a.len() + self.b.len())
+    }
+
+    /// Returns an upper bound on
+    /// [`ratio()`](struct.Differ.html#method.ratio) that is relatively
+    /// quick to compute since it only counts the items the two sequences
+    /// have in common, regardless of their order; this is the equivalent
+    /// of difflib's `quick_ratio()`.
+    pub fn quick_ratio(&self) -> f64 {
+        let mut available: FnvHashMap<&T, usize> = FnvHashMap::default();
+        for item in self.b {
+            *available.entry(item).or_default() += 1;
+        }
+        let mut matched = 0;
+        for item in self.a {
+            if let Some(count) = available.get_mut(item) {
+                if *count > 0 {
+                    *count -= 1;
+                    matched += 1;
+                }
+            }
+        }
+        calculate_ratio(matched, self.a.len() + self.b.len())
+    }
+
+    /// Returns an upper bound on
+    /// [`ratio()`](struct.Differ.html#method.ratio) and
+    /// [`quick_ratio()`](struct.Differ.html#method.quick_ratio) that is
+    /// very quick to compute since it only uses the sequences' lengths;
+    /// this is the equivalent of difflib's `real_quick_ratio()`.
+    pub fn real_quick_ratio(&self) -> f64 {
+        let a_len = self.a.len();
+        let b_len = self.b.len();
+        calculate_ratio(a_len.min(b_len), a_len + b_len)
+    }
+
     /// Returns the longest [`Match`](struct.Match.html) between the two
     /// given sequences, within the given index ranges.
     ///
@@ -169,21 +412,98 @@ where
             }
             j2len = new_j2len;
         }
-        while best_i > a_start
-            && best_j > b_start
-            && self.a[best_i - 1] == self.b[best_j - 1]
-        {
-            best_i -= 1;
-            best_j -= 1;
-            best_size += 1;
+        // Extend the match with non-junk items (popular items won't
+        // have anchored it), and then with junk items.
+        for junk in &[false, true] {
+            while best_i > a_start
+                && best_j > b_start
+                && self.b_junk.contains(&self.b[best_j - 1]) == *junk
+                && self.a[best_i - 1] == self.b[best_j - 1]
+            {
+                best_i -= 1;
+                best_j -= 1;
+                best_size += 1;
+            }
+            while best_i + best_size < a_end
+                && best_j + best_size < b_end
+                && self.b_junk.contains(&self.b[best_j + best_size])
+                    == *junk
+                && self.a[best_i + best_size]
+                    == self.b[best_j + best_size]
+            {
+                best_size += 1;
+            }
         }
-        while best_i + best_size < a_end
-            && best_j + best_size < b_end
-            && self.a[best_i + best_size] == self.b[best_j + best_size]
+        Match::new(best_i, best_j, best_size)
+    }
+}
+
+/// Returns the given spans grouped into [`Hunk`](struct.Hunk.html)s, each
+/// with up to `context` equal items before and after its changes.
+///
+/// Use this if you already have the spans; otherwise use
+/// [`Differ::grouped_spans()`](struct.Differ.html#method.grouped_spans).
+pub fn hunks_for_spans(spans: &[Span], context: usize) -> Vec<Hunk> {
+    let mut spans = spans.to_vec();
+    if spans.is_empty() {
+        return vec![];
+    }
+    let first = &mut spans[0];
+    if first.tag == Tag::Equal {
+        first.a_start =
+            first.a_start.max(first.a_end.saturating_sub(context));
+        first.b_start =
+            first.b_start.max(first.b_end.saturating_sub(context));
+    }
+    let last = spans.last_mut().unwrap();
+    if last.tag == Tag::Equal {
+        last.a_end = last.a_end.min(last.a_start + context);
+        last.b_end = last.b_end.min(last.b_start + context);
+    }
+    let mut hunks = vec![];
+    let mut group = vec![];
+    for mut span in spans {
+        if span.tag == Tag::Equal
+            && span.a_end - span.a_start > 2 * context
         {
-            best_size += 1;
+            group.push(Span::equal(
+                span.a_start,
+                span.a_end.min(span.a_start + context),
+                span.b_start,
+                span.b_end.min(span.b_start + context),
+            ));
+            hunks.push(hunk_for_group(group));
+            group = vec![];
+            span.a_start = span.a_start.max(span.a_end - context);
+            span.b_start = span.b_start.max(span.b_end - context);
         }
-        Match::new(best_i, best_j, best_size)
+        group.push(span);
+    }
+    if !(group.len() == 1 && group[0].tag == Tag::Equal) {
+        hunks.push(hunk_for_group(group));
+    }
+    hunks
+}
+
+fn hunk_for_group(group: Vec<Span>) -> Hunk {
+    let first = &group[0];
+    let last = &group[group.len() - 1];
+    let (a_start, b_start) = (first.a_start, first.b_start);
+    let (a_end, b_end) = (last.a_end, last.b_end);
+    let spans = group
+        .into_iter()
+        .filter(|span| {
+            span.tag != Tag::Equal || span.a_start < span.a_end
+        })
+        .collect();
+    Hunk { a_start, a_end, b_start, b_end, spans }
+}
+
+fn calculate_ratio(matched: usize, length: usize) -> f64 {
+    if length == 0 {
+        1.0
+    } else {
+        2.0 * matched as f64 / length as f64
     }
 }
 
@@ -222,3 +542,335 @@ pub fn spans_for_matches(matches: &[Match]) -> Vec<Span> {
     }
     spans
 }
+
+/// Returns up to `n` of the `possibilities` that are most similar to
+/// `word`, best first; this is the equivalent of difflib's
+/// `get_close_matches()`.
+///
+/// Possibilities are compared character by character using
+/// [`Differ::ratio()`](struct.Differ.html#method.ratio) (after quickly
+/// discarding those whose
+/// [`real_quick_ratio()`](struct.Differ.html#method.real_quick_ratio) or
+/// [`quick_ratio()`](struct.Differ.html#method.quick_ratio) is too low),
+/// and those with a ratio less than `cutoff` are ignored. A typical
+/// `cutoff` is `0.6`.
+///
+/// ```
+/// use differ::close_matches;
+///
+/// let commands = ["status", "stash", "start", "stage", "show"];
+/// assert_eq!(
+///     close_matches("stauts", &commands, 3, 0.6),
+///     vec!["status", "stash", "start"]
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if `n` is `0` or if `cutoff` isn't in the range `0.0..=1.0`.
+pub fn close_matches<'c>(
+    word: &str,
+    possibilities: &[&'c str],
+    n: usize,
+    cutoff: f64,
+) -> Vec<&'c str> {
+    assert!(n > 0, "n must be greater than 0");
+    assert!((0.0..=1.0).contains(&cutoff), "cutoff must be in 0.0..=1.0");
+    let word = word.chars().collect::<Vec<_>>();
+    let candidates = possibilities
+        .iter()
+        .map(|possibility| possibility.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    // The word is sequence b so that its index is only computed once.
+    let mut differ = Differ::new(&[], &word);
+    let mut scored = vec![];
+    for (candidate, possibility) in candidates.iter().zip(possibilities) {
+        differ.set_a(candidate);
+        if differ.real_quick_ratio() >= cutoff
+            && differ.quick_ratio() >= cutoff
+        {
+            let ratio = differ.ratio();
+            if ratio >= cutoff {
+                scored.push((ratio, *possibility));
+            }
+        }
+    }
+    scored.sort_by(|x, y| {
+        y.0.partial_cmp(&x.0)
+            .unwrap_or(Ordering::Equal)
+            .then(y.1.cmp(x.1))
+    });
+    scored
+        .into_iter()
+        .take(n)
+        .map(|(_, possibility)| possibility)
+        .collect()
+}
+
+/// Returns a unified diff (as produced by GNU `diff -u`) that shows how
+/// to convert text `a` into text `b`, with up to `context` lines of
+/// context around each change; this is the equivalent of difflib's
+/// `unified_diff()`.
+///
+/// The file names and dates are used in the `---` and `+++` header lines
+/// (the dates, if not empty, follow a tab, so should be formatted as
+/// required, e.g., `2019-03-08 10:31:02.000000000 +0000`). If the texts
+/// are the same the diff is empty. A last line that doesn't end with a
+/// newline is followed by `\ No newline at end of file`. The standard
+/// `context` is `3`.
+///
+/// The changes are found using GNU `diff`'s own algorithm rather than a
+/// [`Differ`](struct.Differ.html)'s, so even where a change could be
+/// shown in more than one way (e.g., a function inserted between two
+/// others) the output is byte-for-byte the same as that of `diff -u`
+/// with the same context.
+///
+/// ```
+/// use differ::unified_diff;
+///
+/// let a = "one\ntwo\nthree\n";
+/// let b = "one\n2\nthree";
+/// let diff = unified_diff(a, b, "a.txt", "b.txt", "", "", 3);
+/// assert_eq!(
+///     diff,
+///     "--- a.txt\n+++ b.txt\n@@ -1,3 +1,3 @@\n one\n-two\n-three\n+2\n\
+///      +three\n\\ No newline at end of file\n"
+/// );
+/// ```
+///
+/// To use a [`Differ`](struct.Differ.html)'s changes instead (e.g., to
+/// use a particular [`Algorithm`](enum.Algorithm.html), or to control
+/// the matching in some other way), split the texts into lines (keeping
+/// their line endings, e.g., using `split_inclusive()`), get the
+/// [`grouped_spans()`](struct.Differ.html#method.grouped_spans),
+/// and use [`unified_diff_for_hunks()`](fn.unified_diff_for_hunks.html).
+pub fn unified_diff(
+    a: &str,
+    b: &str,
+    from_file: &str,
+    to_file: &str,
+    from_date: &str,
+    to_date: &str,
+    context: usize,
+) -> String {
+    let a_lines = a.split_inclusive('\n').collect::<Vec<_>>();
+    let b_lines = b.split_inclusive('\n').collect::<Vec<_>>();
+    let hunks = gnu_hunks(&a_lines, &b_lines, context);
+    unified_diff_for_hunks(
+        &a_lines, &b_lines, &hunks, from_file, to_file, from_date,
+        to_date,
+    )
+}
+
+/// Returns a unified diff (as produced by GNU `diff -u`) for the given
+/// [`Hunk`](struct.Hunk.html)s of lines `a` and `b`.
+///
+/// The lines should keep their line endings. See
+/// [`unified_diff()`](fn.unified_diff.html) for details.
+pub fn unified_diff_for_hunks(
+    a: &[&str],
+    b: &[&str],
+    hunks: &[Hunk],
+    from_file: &str,
+    to_file: &str,
+    from_date: &str,
+    to_date: &str,
+) -> String {
+    let mut diff = String::new();
+    if hunks.is_empty() {
+        return diff;
+    }
+    push_header(&mut diff, "---", from_file, from_date);
+    push_header(&mut diff, "+++", to_file, to_date);
+    for hunk in hunks {
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            unified_range(hunk.a_start, hunk.a_end),
+            unified_range(hunk.b_start, hunk.b_end)
+        ));
+        for span in &hunk.spans {
+            if span.tag == Tag::Equal {
+                push_lines(&mut diff, " ", &a[span.a_start..span.a_end]);
+                continue;
+            }
+            if span.tag == Tag::Delete || span.tag == Tag::Replace {
+                push_lines(&mut diff, "-", &a[span.a_start..span.a_end]);
+            }
+            if span.tag == Tag::Insert || span.tag == Tag::Replace {
+                push_lines(&mut diff, "+", &b[span.b_start..span.b_end]);
+            }
+        }
+    }
+    diff
+}
+
+// Returns the hunks of the changes that GNU diff finds between lines a
+// and b, each with up to context lines of context.
+fn gnu_hunks(a: &[&str], b: &[&str], context: usize) -> Vec<Hunk> {
+    let matches = gnu::matches(a, b, context);
+    hunks_for_spans(&spans_for_matches(&matches), context)
+}
+
+// Returns a "start,length" range as used in unified diffs, where the
+// length is omitted if it is 1, and the start is the line before if the
+// range is empty.
+pub(crate) fn unified_range(start: usize, end: usize) -> String {
+    match end - start {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        length => format!("{},{}", start + 1, length),
+    }
+}
+
+pub(crate) fn push_header(
+    diff: &mut String,
+    prefix: &str,
+    file: &str,
+    date: &str,
+) {
+    diff.push_str(prefix);
+    diff.push(' ');
+    diff.push_str(file);
+    if !date.is_empty() {
+        diff.push('\t');
+        diff.push_str(date);
+    }
+    diff.push('\n');
+}
+
+pub(crate) fn push_lines(
+    diff: &mut String,
+    prefix: &str,
+    lines: &[&str],
+) {
+    for line in lines {
+        diff.push_str(prefix);
+        diff.push_str(line);
+        if !line.ends_with('\n') {
+            diff.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Returns a context diff (as produced by GNU `diff -c`) that shows how
+/// to convert text `a` into text `b`, with up to `context` lines of
+/// context around each change; this is the equivalent of difflib's
+/// `context_diff()`.
+///
+/// The file names and dates are used in the `***` and `---` header lines
+/// just as for [`unified_diff()`](fn.unified_diff.html), and the changes
+/// are found the same way, so the output is byte-for-byte the same as
+/// that of `diff -c` with the same context. Where difflib finds the same
+/// changes (as it usually does unless a change could be shown in more
+/// than one way), the output is also the same as difflib's. The standard
+/// `context` is `3`.
+///
+/// ```
+/// use differ::context_diff;
+///
+/// let a = "one\ntwo\nthree\n";
+/// let b = "one\n2\nthree\nfour\n";
+/// let diff = context_diff(a, b, "a.txt", "b.txt", "", "", 3);
+/// assert_eq!(
+///     diff,
+///     "*** a.txt\n--- b.txt\n***************\n*** 1,3 ****\n  one\n\
+///      ! two\n  three\n--- 1,4 ----\n  one\n! 2\n  three\n+ four\n"
+/// );
+/// ```
+pub fn context_diff(
+    a: &str,
+    b: &str,
+    from_file: &str,
+    to_file: &str,
+    from_date: &str,
+    to_date: &str,
+    context: usize,
+) -> String {
+    let a_lines = a.split_inclusive('\n').collect::<Vec<_>>();
+    let b_lines = b.split_inclusive('\n').collect::<Vec<_>>();
+    let hunks = gnu_hunks(&a_lines, &b_lines, context);
+    context_diff_for_hunks(
+        &a_lines, &b_lines, &hunks, from_file, to_file, from_date,
+        to_date,
+    )
+}
+
+/// Returns a context diff (as produced by GNU `diff -c`) for the given
+/// [`Hunk`](struct.Hunk.html)s of lines `a` and `b`.
+///
+/// The lines should keep their line endings. See
+/// [`context_diff()`](fn.context_diff.html) for details.
+pub fn context_diff_for_hunks(
+    a: &[&str],
+    b: &[&str],
+    hunks: &[Hunk],
+    from_file: &str,
+    to_file: &str,
+    from_date: &str,
+    to_date: &str,
+) -> String {
+    let mut diff = String::new();
+    if hunks.is_empty() {
+        return diff;
+    }
+    push_header(&mut diff, "***", from_file, from_date);
+    push_header(&mut diff, "---", to_file, to_date);
+    for hunk in hunks {
+        diff.push_str("***************\n");
+        diff.push_str(&format!(
+            "*** {} ****\n",
+            context_range(hunk.a_start, hunk.a_end)
+        ));
+        if hunk.spans.iter().any(|span| {
+            span.tag == Tag::Delete || span.tag == Tag::Replace
+        }) {
+            for span in &hunk.spans {
+                if span.tag != Tag::Insert {
+                    push_lines(
+                        &mut diff,
+                        context_prefix(&span.tag),
+                        &a[span.a_start..span.a_end],
+                    );
+                }
+            }
+        }
+        diff.push_str(&format!(
+            "--- {} ----\n",
+            context_range(hunk.b_start, hunk.b_end)
+        ));
+        if hunk.spans.iter().any(|span| {
+            span.tag == Tag::Insert || span.tag == Tag::Replace
+        }) {
+            for span in &hunk.spans {
+                if span.tag != Tag::Delete {
+                    push_lines(
+                        &mut diff,
+                        context_prefix(&span.tag),
+                        &b[span.b_start..span.b_end],
+                    );
+                }
+            }
+        }
+    }
+    diff
+}
+
+// Returns a "first,last" range as used in context diffs, where the last
+// line is omitted if the range has at most one line, and the first is
+// the line before if the range is empty.
+fn context_range(start: usize, end: usize) -> String {
+    match end - start {
+        0 => format!("{}", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, end),
+    }
+}
+
+fn context_prefix(tag: &Tag) -> &'static str {
+    match tag {
+        Tag::Equal => "  ",
+        Tag::Insert => "+ ",
+        Tag::Delete => "- ",
+        Tag::Replace => "! ",
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::structs::{EditCosts, Span};
+use crate::transform::push_run;
+
+const UNREACHABLE: usize = usize::MAX;
+
+/// Provides methods for computing the edit distance between two
+/// sequences and the edits that achieve it.
+///
+/// The edit distance is the lowest total cost of the insertions,
+/// deletions, and substitutions (and optionally, transpositions of
+/// adjacent items) that convert sequence `a` into `b`. Unlike
+/// [`Differ`](struct.Differ.html), which finds long matching blocks, this
+/// finds a truly minimal set of edits, so is best suited to short
+/// sequences such as words or identifiers.
+///
+/// ```
+/// use differ::EditDistance;
+///
+/// let a = "kitten".chars().collect::<Vec<_>>();
+/// let b = "sitting".chars().collect::<Vec<_>>();
+/// let distance = EditDistance::new(&a, &b);
+/// assert_eq!(distance.distance(), Some(3));
+/// assert_eq!(distance.max_distance(2).distance(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EditDistance<'a, T>
+where
+    T: 'a + Eq,
+{
+    a: &'a [T],
+    b: &'a [T],
+    costs: EditCosts,
+    transpositions: bool,
+    max_distance: Option<usize>,
+}
+
+impl<'a, T> EditDistance<'a, T>
+where
+    T: 'a + Eq,
+{
+    /// Creates a new `EditDistance` for converting sequence `a` into `b`
+    /// that computes the Levenshtein distance, i.e., with every edit
+    /// costing `1` and no transpositions.
+    pub fn new(a: &'a [T], b: &'a [T]) -> Self {
+        EditDistance {
+            a,
+            b,
+            costs: EditCosts::default(),
+            transpositions: false,
+            max_distance: None,
+        }
+    }
+
+    /// Sets whether swapping two adjacent items counts as a single edit
+    /// (the default is `false`).
+    ///
+    /// When on, this computes the Damerau-Levenshtein distance in its
+    /// _optimal string alignment_ form, i.e., no item may be edited again
+    /// after it has been transposed.
+    ///
+    /// ```
+    /// use differ::EditDistance;
+    ///
+    /// let a = "teh".chars().collect::<Vec<_>>();
+    /// let b = "the".chars().collect::<Vec<_>>();
+    /// let distance = EditDistance::new(&a, &b);
+    /// assert_eq!(distance.distance(), Some(2));
+    /// assert_eq!(distance.transpositions(true).distance(), Some(1));
+    /// ```
+    pub fn transpositions(mut self, transpositions: bool) -> Self {
+        self.transpositions = transpositions;
+        self
+    }
+
+    /// Sets the cost of each kind of edit (see
+    /// [`EditCosts`](struct.EditCosts.html)).
+    ///
+    /// ```
+    /// use differ::{EditCosts, EditDistance};
+    ///
+    /// let a = "abc".chars().collect::<Vec<_>>();
+    /// let b = "axc".chars().collect::<Vec<_>>();
+    /// let costs = EditCosts { substitute: 3, ..EditCosts::default() };
+    /// // A deletion and an insertion are cheaper than a substitution
+    /// let distance = EditDistance::new(&a, &b).costs(costs);
+    /// assert_eq!(distance.distance(), Some(2));
+    /// ```
+    pub fn costs(mut self, costs: EditCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Sets the largest distance of interest: the computation stops as
+    /// soon as the distance is known to be greater than `max_distance`
+    /// and only the edits that could stay within it are considered.
+    /// This makes comparing, e.g., an identifier against many candidates
+    /// much faster.
+    pub fn max_distance(mut self, max_distance: usize) -> Self {
+        self.max_distance = Some(max_distance);
+        self
+    }
+
+    /// Returns the edit distance, or `None` if it is greater than the
+    /// [`max_distance()`](struct.EditDistance.html#method.max_distance).
+    pub fn distance(&self) -> Option<usize> {
+        let rows = self.rows(false)?;
+        let distance = rows[rows.len() - 1].get(self.b.len());
+        self.within(distance)
+    }
+
+    /// Returns the spans that convert sequence `a` into `b` with the
+    /// fewest (or cheapest) edits, or `None` if the edit distance is
+    /// greater than the
+    /// [`max_distance()`](struct.EditDistance.html#method.max_distance).
+    ///
+    /// Adjacent edits are combined into a single span, so a substitution
+    /// or transposition is part of a `Tag::Replace` span.
+    ///
+    /// ```
+    /// use differ::{EditDistance, Span};
+    ///
+    /// let a = "recieve".chars().collect::<Vec<_>>();
+    /// let b = "receive".chars().collect::<Vec<_>>();
+    /// let distance = EditDistance::new(&a, &b).transpositions(true);
+    /// assert_eq!(
+    ///     distance.spans(),
+    ///     Some(vec![
+    ///         Span::equal(0, 3, 0, 3),
+    ///         Span::replace(3, 5, 3, 5), // "ie" -> "ei"
+    ///         Span::equal(5, 7, 5, 7),
+    ///     ])
+    /// );
+    /// ```
+    pub fn spans(&self) -> Option<Vec<Span>> {
+        let rows = self.rows(true)?;
+        let (a, b) = (self.a, self.b);
+        let (mut i, mut j) = (a.len(), b.len());
+        self.within(rows[i].get(j))?;
+        let costs = &self.costs;
+        let mut runs = vec![]; // (equal, a_len, b_len)
+        while i > 0 || j > 0 {
+            let distance = rows[i].get(j);
+            let diagonal = i > 0 && j > 0;
+            if diagonal
+                && a[i - 1] == b[j - 1]
+                && rows[i - 1].get(j - 1) == distance
+            {
+                runs.push((true, 1, 1));
+            } else if diagonal
+                && a[i - 1] != b[j - 1]
+                && rows[i - 1].get(j - 1).saturating_add(costs.substitute)
+                    == distance
+            {
+                runs.push((false, 1, 1));
+            } else if self.transposed(i, j)
+                && rows[i - 2].get(j - 2).saturating_add(costs.transpose)
+                    == distance
+            {
+                runs.push((false, 2, 2));
+            } else if i > 0
+                && rows[i - 1].get(j).saturating_add(costs.delete)
+                    == distance
+            {
+                runs.push((false, 1, 0));
+            } else {
+                runs.push((false, 0, 1));
+            }
+            let (_, a_len, b_len) = runs[runs.len() - 1];
+            i -= a_len;
+            j -= b_len;
+        }
+        let mut spans = vec![];
+        for (equal, a_len, b_len) in runs.into_iter().rev() {
+            push_run(&mut spans, equal, a_len, b_len);
+        }
+        Some(spans)
+    }
+
+    fn within(&self, distance: usize) -> Option<usize> {
+        match self.max_distance {
+            Some(max_distance) if distance > max_distance => None,
+            _ => Some(distance),
+        }
+    }
+
+    // Returns true if a[i - 2..i] is b[j - 2..j] with its items swapped
+    // and transpositions are on.
+    fn transposed(&self, i: usize, j: usize) -> bool {
+        self.transpositions
+            && i > 1
+            && j > 1
+            && self.a[i - 1] == self.b[j - 2]
+            && self.a[i - 2] == self.b[j - 1]
+    }
+
+    // Returns the rows of the distance table (all of them if keep_all is
+    // true, otherwise only the last three), where rows[i].get(j) is the
+    // distance between a[..i] and b[..j], or None if the distances are
+    // certain to be greater than the max_distance. When there's a
+    // max_distance only the band of cells it can reach is computed and
+    // stored.
+    fn rows(&self, keep_all: bool) -> Option<Vec<Row>> {
+        let (a, b) = (self.a, self.b);
+        let costs = &self.costs;
+        let reach = |cost: usize| match self.max_distance {
+            Some(max_distance) if cost > 0 => max_distance / cost,
+            _ => usize::MAX,
+        };
+        let (below, above) = (reach(costs.delete), reach(costs.insert));
+        let mut rows: Vec<Row> = vec![];
+        for i in 0..=a.len() {
+            let first = i.saturating_sub(below);
+            let last = b.len().min(i.saturating_add(above));
+            let mut row = Row { first, distances: vec![] };
+            for j in first..=last {
+                if i == 0 && j == 0 {
+                    row.distances.push(0);
+                    continue;
+                }
+                let mut distance = UNREACHABLE;
+                if let Some(previous) = rows.last() {
+                    distance =
+                        previous.get(j).saturating_add(costs.delete);
+                    if j > 0 {
+                        let cost = if a[i - 1] == b[j - 1] {
+                            0
+                        } else {
+                            costs.substitute
+                        };
+                        distance = distance.min(
+                            previous.get(j - 1).saturating_add(cost),
+                        );
+                    }
+                }
+                if j > 0 {
+                    distance = distance
+                        .min(row.get(j - 1).saturating_add(costs.insert));
+                }
+                if self.transposed(i, j) {
+                    let before = &rows[rows.len() - 2];
+                    distance = distance.min(
+                        before.get(j - 2).saturating_add(costs.transpose),
+                    );
+                }
+                row.distances.push(distance);
+            }
+            if let Some(max_distance) = self.max_distance {
+                // A transposition skips a row, so then two in a row must
+                // be out of reach
+                let out_of_reach = |row: &Row| {
+                    row.distances
+                        .iter()
+                        .all(|&distance| distance > max_distance)
+                };
+                if out_of_reach(&row)
+                    && (!self.transpositions
+                        || matches!(
+                            rows.last(),
+                            Some(previous) if out_of_reach(previous)
+                        ))
+                {
+                    return None;
+                }
+            }
+            rows.push(row);
+            if !keep_all && rows.len() > 3 {
+                rows.remove(0);
+            }
+        }
+        Some(rows)
+    }
+}
+
+// One row of the distance table: only the distances from index first
+// onwards that were computed are stored; the rest are unreachable.
+struct Row {
+    first: usize,
+    distances: Vec<usize>,
+}
+
+impl Row {
+    fn get(&self, j: usize) -> usize {
+        j.checked_sub(self.first)
+            .and_then(|j| self.distances.get(j))
+            .copied()
+            .unwrap_or(UNREACHABLE)
+    }
+}
+
+/// Returns the Levenshtein distance between sequences `a` and `b`, i.e.,
+/// the fewest insertions, deletions, and substitutions that convert `a`
+/// into `b`.
+///
+/// This is a shortcut for `EditDistance::new(a, b).distance()`; see
+/// [`EditDistance`](struct.EditDistance.html) for custom costs, a
+/// maximum distance, and the spans.
+///
+/// ```
+/// use differ::levenshtein;
+///
+/// assert_eq!(levenshtein(b"flaw", b"lawn"), 2);
+/// ```
+pub fn levenshtein<T: Eq>(a: &[T], b: &[T]) -> usize {
+    EditDistance::new(a, b).distance().unwrap_or_default()
+}
+
+/// Returns the (optimal string alignment) Damerau-Levenshtein distance
+/// between sequences `a` and `b`, i.e., the fewest insertions, deletions,
+/// substitutions, and transpositions of adjacent items that convert `a`
+/// into `b`, with no item edited again after being transposed.
+///
+/// ```
+/// use differ::damerau_levenshtein;
+///
+/// assert_eq!(damerau_levenshtein(b"ca", b"ac"), 1);
+/// assert_eq!(damerau_levenshtein(b"ca", b"abc"), 3);
+/// ```
+pub fn damerau_levenshtein<T: Eq>(a: &[T], b: &[T]) -> usize {
+    EditDistance::new(a, b)
+        .transpositions(true)
+        .distance()
+        .unwrap_or_default()
+}
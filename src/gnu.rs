@@ -0,0 +1,616 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::myers;
+use crate::structs::Match;
+use fnv::FnvHashMap;
+use std::hash::Hash;
+
+/// Returns the matches between `a` and `b` that GNU `diff` finds.
+///
+/// This is a port of GNU diffutils' comparison (analyze.c and gnulib's
+/// diffseq.h): the common prefix and suffix are set aside except for
+/// `horizon` lines of each, lines that can't match (or that match so
+/// many lines that they would only confuse things) are discarded, the
+/// rest are compared using Myers' algorithm with GNU's choice of middle
+/// snake, and finally each change is slid to where GNU puts it. GNU
+/// `diff` uses its context size (e.g., `3` for `-u`) as the horizon.
+///
+/// The matches are in order and are followed by a zero length match at
+/// the ends of `a` and `b`, just like those returned by
+/// [`Differ::matches()`](struct.Differ.html#method.matches).
+pub(crate) fn matches<T: Hash + Eq>(
+    a: &[T],
+    b: &[T],
+    horizon: usize,
+) -> Vec<Match> {
+    let prefix = myers::common_prefix(a, 0, a.len(), b, 0, b.len());
+    let suffix =
+        myers::common_suffix(a, prefix, a.len(), b, prefix, b.len());
+    let start = prefix - prefix.min(horizon);
+    let suffix = suffix - suffix.min(horizon);
+    let a = &a[start..a.len() - suffix];
+    let b = &b[start..b.len() - suffix];
+    let mut classes = FnvHashMap::default();
+    let mut class = |item| {
+        let next = classes.len() + 1;
+        *classes.entry(item).or_insert(next)
+    };
+    let a_equivs = a.iter().map(&mut class).collect::<Vec<_>>();
+    let b_equivs = b.iter().map(&mut class).collect::<Vec<_>>();
+    let mut files = [File::new(a_equivs), File::new(b_equivs)];
+    discard_confusing_lines(&mut files, classes.len() + 1);
+    compare(&mut files);
+    shift_boundaries(&mut files);
+    // The lines that are unchanged in both files match each other in
+    // order
+    let mut matches = vec![];
+    let mut add =
+        |i: usize, j: usize, length: usize| match matches.last_mut() {
+            Some(Match { a_start, b_start, length: last })
+                if *a_start + *last == i && *b_start + *last == j =>
+            {
+                *last += length
+            }
+            _ if length > 0 => matches.push(Match::new(i, j, length)),
+            _ => {}
+        };
+    add(0, 0, start);
+    let (a_changed, b_changed) = (&files[0].changed, &files[1].changed);
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a_changed[i + 1] {
+            i += 1;
+        } else if b_changed[j + 1] {
+            j += 1;
+        } else {
+            add(start + i, start + j, 1);
+            i += 1;
+            j += 1;
+        }
+    }
+    add(start + a.len(), start + b.len(), suffix);
+    matches.push(Match::new(
+        start + a.len() + suffix,
+        start + b.len() + suffix,
+        0,
+    ));
+    matches
+}
+
+// One of the sequences being compared.
+struct File {
+    // The equivalence class of each line
+    equivs: Vec<usize>,
+    // Whether each line is changed, with line i at i + 1 so that there's
+    // an unchanged line before the first and after the last
+    changed: Vec<bool>,
+    // The equivalence classes that are compared and the lines they're of
+    undiscarded: Vec<usize>,
+    real_indexes: Vec<usize>,
+}
+
+impl File {
+    fn new(equivs: Vec<usize>) -> Self {
+        let changed = vec![false; equivs.len() + 2];
+        File {
+            equivs,
+            changed,
+            undiscarded: vec![],
+            real_indexes: vec![],
+        }
+    }
+}
+
+// Discards the lines that match no line of the other file, and the runs
+// of lines that match many lines of the other file, marking them as
+// changed.
+fn discard_confusing_lines(files: &mut [File; 2], classes: usize) {
+    let mut counts = [vec![0; classes], vec![0; classes]];
+    for (file, counts) in files.iter().zip(counts.iter_mut()) {
+        for &equiv in &file.equivs {
+            counts[equiv] += 1;
+        }
+    }
+    for f in 0..2 {
+        let equivs = &files[f].equivs;
+        let end = equivs.len();
+        // 1 means discard, 2 means discard provisionally
+        let mut discards = vec![0; end];
+        // Lines that match more than many lines (which grows with the
+        // square root of the number of lines) are provisionally
+        // discardable
+        let mut many = 5;
+        let mut tem = end / 64;
+        loop {
+            tem >>= 2;
+            if tem == 0 {
+                break;
+            }
+            many *= 2;
+        }
+        for i in 0..end {
+            let matches = counts[1 - f][equivs[i]];
+            if matches == 0 {
+                discards[i] = 1;
+            } else if matches > many {
+                discards[i] = 2;
+            }
+        }
+        keep_provisionals(&mut discards);
+        let file = &mut files[f];
+        for (i, &discard) in discards.iter().enumerate() {
+            if discard == 0 {
+                file.undiscarded.push(file.equivs[i]);
+                file.real_indexes.push(i);
+            } else {
+                file.changed[i + 1] = true;
+            }
+        }
+    }
+}
+
+// Cancels the provisional discards except those in the middle of runs of
+// discards, and in runs that have too many of them.
+fn keep_provisionals(discards: &mut [u8]) {
+    let end = discards.len() as isize;
+    let mut i = 0;
+    while i < end {
+        if discards[i as usize] == 2 {
+            discards[i as usize] = 0;
+        } else if discards[i as usize] != 0 {
+            // Find the end of this run and count its provisionals
+            let mut provisional = 0;
+            let mut j = i;
+            while j < end && discards[j as usize] != 0 {
+                if discards[j as usize] == 2 {
+                    provisional += 1;
+                }
+                j += 1;
+            }
+            while j > i && discards[(j - 1) as usize] == 2 {
+                j -= 1;
+                discards[j as usize] = 0;
+                provisional -= 1;
+            }
+            let length = j - i;
+            if provisional * 4 > length {
+                for discard in &mut discards[i as usize..j as usize] {
+                    if *discard == 2 {
+                        *discard = 0;
+                    }
+                }
+            } else {
+                let mut minimum = 1;
+                let mut tem = length >> 2;
+                loop {
+                    tem >>= 2;
+                    if tem <= 0 {
+                        break;
+                    }
+                    minimum <<= 1;
+                }
+                minimum += 1;
+                // Cancel any subrun of minimum or more provisionals
+                let mut consec = 0;
+                let mut j = 0;
+                while j < length {
+                    let k = (i + j) as usize;
+                    if discards[k] != 2 {
+                        consec = 0;
+                    } else {
+                        consec += 1;
+                        if consec == minimum {
+                            j -= consec;
+                        } else if consec > minimum {
+                            discards[k] = 0;
+                        }
+                    }
+                    j += 1;
+                }
+                // Cancel the provisionals at the start of the run until
+                // there are 3 nonprovisionals in a row or one at least 8
+                // lines in, and likewise at the end
+                cancel_leading(discards, i, length, 1);
+                i += length - 1;
+                cancel_leading(discards, i, length, -1);
+            }
+        }
+        i += 1;
+    }
+}
+
+fn cancel_leading(
+    discards: &mut [u8],
+    i: isize,
+    length: isize,
+    step: isize,
+) {
+    let mut consec = 0;
+    for j in 0..length {
+        let k = (i + j * step) as usize;
+        if j >= 8 && discards[k] == 1 {
+            break;
+        }
+        if discards[k] == 2 {
+            consec = 0;
+            discards[k] = 0;
+        } else if discards[k] == 0 {
+            consec = 0;
+        } else {
+            consec += 1;
+        }
+        if consec == 3 {
+            break;
+        }
+    }
+}
+
+// Marks the lines that are changed between the undiscarded lines.
+fn compare(files: &mut [File; 2]) {
+    let (x_len, y_len) =
+        (files[0].undiscarded.len(), files[1].undiscarded.len());
+    // GNU settles for a good enough diff once the edit cost reaches
+    // roughly the square root of the number of lines (but at least 4096)
+    let mut diagonals = x_len + y_len + 3;
+    let mut too_expensive: isize = 1;
+    while diagonals != 0 {
+        too_expensive <<= 1;
+        diagonals >>= 2;
+    }
+    let mut context = Context {
+        x: &files[0].undiscarded,
+        y: &files[1].undiscarded,
+        forward: vec![0; x_len + y_len + 3],
+        backward: vec![0; x_len + y_len + 3],
+        offset: y_len as isize + 1,
+        too_expensive: too_expensive.max(4096),
+        deleted: vec![],
+        inserted: vec![],
+    };
+    context.compare(0, x_len as isize, 0, y_len as isize, false);
+    let (deleted, inserted) = (context.deleted, context.inserted);
+    for x in deleted {
+        let i = files[0].real_indexes[x];
+        files[0].changed[i + 1] = true;
+    }
+    for y in inserted {
+        let j = files[1].real_indexes[y];
+        files[1].changed[j + 1] = true;
+    }
+}
+
+struct Context<'a> {
+    x: &'a [usize],
+    y: &'a [usize],
+    // The furthest x reached on each diagonal going forward and backward
+    forward: Vec<isize>,
+    backward: Vec<isize>,
+    offset: isize, // of diagonal 0 in forward and backward
+    too_expensive: isize,
+    deleted: Vec<usize>,
+    inserted: Vec<usize>,
+}
+
+// Where to split a region, and whether each half must be diffed
+// minimally.
+struct Partition {
+    x_mid: isize,
+    y_mid: isize,
+    lo_minimal: bool,
+    hi_minimal: bool,
+}
+
+impl<'a> Context<'a> {
+    fn equal(&self, x: isize, y: isize) -> bool {
+        self.x[x as usize] == self.y[y as usize]
+    }
+
+    fn fd(&mut self, d: isize) -> &mut isize {
+        &mut self.forward[(d + self.offset) as usize]
+    }
+
+    fn bd(&mut self, d: isize) -> &mut isize {
+        &mut self.backward[(d + self.offset) as usize]
+    }
+
+    fn compare(
+        &mut self,
+        mut x_off: isize,
+        mut x_lim: isize,
+        mut y_off: isize,
+        mut y_lim: isize,
+        minimal: bool,
+    ) {
+        while x_off < x_lim && y_off < y_lim && self.equal(x_off, y_off) {
+            x_off += 1;
+            y_off += 1;
+        }
+        while x_off < x_lim
+            && y_off < y_lim
+            && self.equal(x_lim - 1, y_lim - 1)
+        {
+            x_lim -= 1;
+            y_lim -= 1;
+        }
+        if x_off == x_lim {
+            self.inserted.extend(y_off as usize..y_lim as usize);
+        } else if y_off == y_lim {
+            self.deleted.extend(x_off as usize..x_lim as usize);
+        } else {
+            let part = self.diag(x_off, x_lim, y_off, y_lim, minimal);
+            self.compare(
+                x_off,
+                part.x_mid,
+                y_off,
+                part.y_mid,
+                part.lo_minimal,
+            );
+            self.compare(
+                part.x_mid,
+                x_lim,
+                part.y_mid,
+                y_lim,
+                part.hi_minimal,
+            );
+        }
+    }
+
+    // Returns the midpoint of the shortest edit script for the region
+    // (or, if that's too expensive to find, a good guess at one).
+    fn diag(
+        &mut self,
+        x_off: isize,
+        x_lim: isize,
+        y_off: isize,
+        y_lim: isize,
+        minimal: bool,
+    ) -> Partition {
+        let d_min = x_off - y_lim;
+        let d_max = x_lim - y_off;
+        let f_mid = x_off - y_off;
+        let b_mid = x_lim - y_lim;
+        let (mut f_min, mut f_max) = (f_mid, f_mid);
+        let (mut b_min, mut b_max) = (b_mid, b_mid);
+        let odd = (f_mid - b_mid) & 1 != 0;
+        *self.fd(f_mid) = x_off;
+        *self.bd(b_mid) = x_lim;
+        let mut cost = 1;
+        loop {
+            // Extend the forward search by an edit on each diagonal
+            if f_min > d_min {
+                f_min -= 1;
+                *self.fd(f_min - 1) = -1;
+            } else {
+                f_min += 1;
+            }
+            if f_max < d_max {
+                f_max += 1;
+                *self.fd(f_max + 1) = -1;
+            } else {
+                f_max -= 1;
+            }
+            let mut d = f_max;
+            while d >= f_min {
+                let (lo, hi) = (*self.fd(d - 1), *self.fd(d + 1));
+                let mut x = if lo < hi { hi } else { lo + 1 };
+                let mut y = x - d;
+                while x < x_lim && y < y_lim && self.equal(x, y) {
+                    x += 1;
+                    y += 1;
+                }
+                *self.fd(d) = x;
+                if odd && b_min <= d && d <= b_max && *self.bd(d) <= x {
+                    return Partition {
+                        x_mid: x,
+                        y_mid: y,
+                        lo_minimal: true,
+                        hi_minimal: true,
+                    };
+                }
+                d -= 2;
+            }
+            // And the backward search
+            if b_min > d_min {
+                b_min -= 1;
+                *self.bd(b_min - 1) = isize::MAX;
+            } else {
+                b_min += 1;
+            }
+            if b_max < d_max {
+                b_max += 1;
+                *self.bd(b_max + 1) = isize::MAX;
+            } else {
+                b_max -= 1;
+            }
+            let mut d = b_max;
+            while d >= b_min {
+                let (lo, hi) = (*self.bd(d - 1), *self.bd(d + 1));
+                let mut x = if lo < hi { lo } else { hi - 1 };
+                let mut y = x - d;
+                while x_off < x && y_off < y && self.equal(x - 1, y - 1) {
+                    x -= 1;
+                    y -= 1;
+                }
+                *self.bd(d) = x;
+                if !odd && f_min <= d && d <= f_max && x <= *self.fd(d) {
+                    return Partition {
+                        x_mid: x,
+                        y_mid: y,
+                        lo_minimal: true,
+                        hi_minimal: true,
+                    };
+                }
+                d -= 2;
+            }
+            if !minimal && cost >= self.too_expensive {
+                // Settle for whichever of the forward and backward
+                // searches has got furthest
+                let (fx, fy) =
+                    self.forward_best(f_min, f_max, x_lim, y_lim);
+                let (bx, by) =
+                    self.backward_best(b_min, b_max, x_off, y_off);
+                return if (x_lim + y_lim) - (bx + by)
+                    < (fx + fy) - (x_off + y_off)
+                {
+                    Partition {
+                        x_mid: fx,
+                        y_mid: fy,
+                        lo_minimal: true,
+                        hi_minimal: false,
+                    }
+                } else {
+                    Partition {
+                        x_mid: bx,
+                        y_mid: by,
+                        lo_minimal: false,
+                        hi_minimal: true,
+                    }
+                };
+            }
+            cost += 1;
+        }
+    }
+
+    // Returns the point furthest along the forward diagonals.
+    fn forward_best(
+        &mut self,
+        f_min: isize,
+        f_max: isize,
+        x_lim: isize,
+        y_lim: isize,
+    ) -> (isize, isize) {
+        let mut best = (-1, 0);
+        let mut d = f_max;
+        while d >= f_min {
+            let mut x = (*self.fd(d)).min(x_lim);
+            let mut y = x - d;
+            if y_lim < y {
+                x = y_lim + d;
+                y = y_lim;
+            }
+            if best.0 + best.1 < x + y {
+                best = (x, y);
+            }
+            d -= 2;
+        }
+        best
+    }
+
+    // Returns the point furthest along the backward diagonals.
+    fn backward_best(
+        &mut self,
+        b_min: isize,
+        b_max: isize,
+        x_off: isize,
+        y_off: isize,
+    ) -> (isize, isize) {
+        let mut best = (isize::MAX, 0);
+        let mut d = b_max;
+        while d >= b_min {
+            let mut x = (*self.bd(d)).max(x_off);
+            let mut y = x - d;
+            if y < y_off {
+                x = y_off + d;
+                y = y_off;
+            }
+            if x + y < best.0 + best.1 {
+                best = (x, y);
+            }
+            d -= 2;
+        }
+        best
+    }
+}
+
+// Slides each run of changed lines up to merge it with any earlier runs,
+// then down as far as possible to merge it with any later runs, and then
+// back up to line up with a run of changes in the other file, if it
+// passed one.
+fn shift_boundaries(files: &mut [File; 2]) {
+    let at = |i: isize| (i + 1) as usize;
+    for f in 0..2 {
+        let (first, second) = files.split_at_mut(1);
+        let (file, other) = if f == 0 {
+            (&mut first[0], &second[0])
+        } else {
+            (&mut second[0], &first[0])
+        };
+        let (equivs, changed) = (&file.equivs, &mut file.changed);
+        let other_changed = &other.changed;
+        let i_end = equivs.len() as isize;
+        let (mut i, mut j) = (0, 0);
+        loop {
+            // Find the start of the next run of changes and the
+            // corresponding point in the other file
+            while i < i_end && !changed[at(i)] {
+                while other_changed[at(j)] {
+                    j += 1;
+                }
+                j += 1;
+                i += 1;
+            }
+            if i == i_end {
+                break;
+            }
+            let mut start = i;
+            i += 1;
+            while changed[at(i)] {
+                i += 1;
+            }
+            while other_changed[at(j)] {
+                j += 1;
+            }
+            let mut corresponding;
+            loop {
+                let run_length = i - start;
+                while start > 0
+                    && equivs[(start - 1) as usize]
+                        == equivs[(i - 1) as usize]
+                {
+                    start -= 1;
+                    changed[at(start)] = true;
+                    i -= 1;
+                    changed[at(i)] = false;
+                    while changed[at(start - 1)] {
+                        start -= 1;
+                    }
+                    j -= 1;
+                    while other_changed[at(j)] {
+                        j -= 1;
+                    }
+                }
+                corresponding =
+                    if other_changed[at(j - 1)] { i } else { i_end };
+                while i != i_end
+                    && equivs[start as usize] == equivs[i as usize]
+                {
+                    changed[at(start)] = false;
+                    start += 1;
+                    changed[at(i)] = true;
+                    i += 1;
+                    while changed[at(i)] {
+                        i += 1;
+                    }
+                    j += 1;
+                    while other_changed[at(j)] {
+                        j += 1;
+                        corresponding = i;
+                    }
+                }
+                if run_length == i - start {
+                    break;
+                }
+            }
+            while corresponding < i {
+                start -= 1;
+                changed[at(start)] = true;
+                i -= 1;
+                changed[at(i)] = false;
+                j -= 1;
+                while other_changed[at(j)] {
+                    j -= 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::myers;
+use crate::structs::Match;
+use fnv::FnvHashMap;
+use std::hash::Hash;
+
+// Items occurring more often than this in a region aren't used as
+// anchors; if every candidate is this common the region is handed to
+// Myers instead (as git and jgit do).
+const MAX_CHAIN: usize = 64;
+
+/// Returns the matches between `a` and `b` using the histogram diff
+/// algorithm (as used by git's `--histogram`).
+///
+/// For each region an occurrence index of `b` is built (like
+/// `Differ::chain_b_seq()`'s `b2j`), and the longest common run
+/// containing the _least_ frequent items is chosen as the anchor; the
+/// regions either side of it are then diffed in the same way.
+///
+/// The matches are unsorted and may be adjacent: the caller is expected
+/// to sort and coalesce them.
+pub(crate) fn matches<T: Hash + Eq>(a: &[T], b: &[T]) -> Vec<Match> {
+    let mut matches = vec![];
+    let mut queue = vec![(0, a.len(), 0, b.len())];
+    while let Some((mut a_start, mut a_end, mut b_start, mut b_end)) =
+        queue.pop()
+    {
+        let prefix =
+            myers::common_prefix(a, a_start, a_end, b, b_start, b_end);
+        if prefix > 0 {
+            matches.push(Match::new(a_start, b_start, prefix));
+            a_start += prefix;
+            b_start += prefix;
+        }
+        let suffix =
+            myers::common_suffix(a, a_start, a_end, b, b_start, b_end);
+        if suffix > 0 {
+            a_end -= suffix;
+            b_end -= suffix;
+            matches.push(Match::new(a_end, b_end, suffix));
+        }
+        if a_start == a_end || b_start == b_end {
+            continue;
+        }
+        match best_anchor(a, a_start, a_end, b, b_start, b_end) {
+            Anchor::Found(m) => {
+                queue.push((a_start, m.a_start, b_start, m.b_start));
+                queue.push((
+                    m.a_start + m.length,
+                    a_end,
+                    m.b_start + m.length,
+                    b_end,
+                ));
+                matches.push(m);
+            }
+            Anchor::TooCommon => matches.extend(myers::matches_in(
+                a, a_start, a_end, b, b_start, b_end,
+            )),
+            Anchor::NoneCommon => (),
+        }
+    }
+    matches
+}
+
+enum Anchor {
+    Found(Match),
+    TooCommon,
+    NoneCommon,
+}
+
+fn best_anchor<T: Hash + Eq>(
+    a: &[T],
+    a_start: usize,
+    a_end: usize,
+    b: &[T],
+    b_start: usize,
+    b_end: usize,
+) -> Anchor {
+    let mut b2j: FnvHashMap<&T, Vec<usize>> = FnvHashMap::default();
+    for (j, item) in b.iter().enumerate().take(b_end).skip(b_start) {
+        b2j.entry(item).or_default().push(j);
+    }
+    let mut best = Match::new(a_start, b_start, 0);
+    let mut best_count = MAX_CHAIN + 1;
+    let mut any_common = false;
+    let mut i = a_start;
+    while i < a_end {
+        let mut next_i = i + 1;
+        if let Some(indexes) = b2j.get(&a[i]) {
+            any_common = true;
+            if indexes.len() <= best_count.min(MAX_CHAIN) {
+                for &j in indexes {
+                    let mut start_i = i;
+                    let mut start_j = j;
+                    while start_i > a_start
+                        && start_j > b_start
+                        && a[start_i - 1] == b[start_j - 1]
+                    {
+                        start_i -= 1;
+                        start_j -= 1;
+                    }
+                    let mut end_i = i + 1;
+                    let mut end_j = j + 1;
+                    while end_i < a_end
+                        && end_j < b_end
+                        && a[end_i] == b[end_j]
+                    {
+                        end_i += 1;
+                        end_j += 1;
+                    }
+                    let count = a[start_i..end_i]
+                        .iter()
+                        .map(|item| b2j[item].len())
+                        .min()
+                        .unwrap_or(0);
+                    let length = end_i - start_i;
+                    if count < best_count
+                        || (count == best_count && length > best.length)
+                    {
+                        best = Match::new(start_i, start_j, length);
+                        best_count = count;
+                    }
+                    next_i = next_i.max(end_i);
+                }
+            }
+        }
+        i = next_i;
+    }
+    if best.length > 0 {
+        Anchor::Found(best)
+    } else if any_common {
+        Anchor::TooCommon
+    } else {
+        Anchor::NoneCommon
+    }
+}
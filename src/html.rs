@@ -0,0 +1,267 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::differ::{hunks_for_spans, Differ};
+use crate::structs::{Span, Tag};
+
+/// Produces side-by-side HTML tables showing the differences between two
+/// sequences of lines, with line numbers and intraline highlighting of
+/// changes; this is the equivalent of difflib's `HtmlDiff`.
+///
+/// Changes are marked up using `<span>`s of class `diff_add` (inserted),
+/// `diff_sub` (deleted), and `diff_chg` (changed within a replaced line);
+/// line numbers are in `<td>`s of class `diff_header`. See
+/// [`file()`](struct.HtmlDiff.html#method.file) for a complete page
+/// including a suitable style sheet.
+///
+/// ```
+/// use differ::HtmlDiff;
+///
+/// let a = ["one\n", "two\n", "three\n"];
+/// let b = ["one\n", "tWo\n", "three\n", "<four>\n"];
+/// let table = HtmlDiff::new().table(&a, &b, "old", "new");
+/// assert!(table.contains(r#"t<span class="diff_chg">W</span>o"#));
+/// assert!(table.contains(r#"<span class="diff_add">&lt;four"#));
+/// ```
+#[derive(Debug, Clone)]
+pub struct HtmlDiff {
+    tab_size: usize,
+    context: Option<usize>,
+}
+
+impl Default for HtmlDiff {
+    fn default() -> Self {
+        HtmlDiff::new()
+    }
+}
+
+impl HtmlDiff {
+    /// Creates a new `HtmlDiff` that shows every line, with tabs
+    /// expanded to every 8th column.
+    pub fn new() -> Self {
+        HtmlDiff { tab_size: 8, context: None }
+    }
+
+    /// Sets the tab stop interval used to expand tabs (the default is
+    /// `8`).
+    pub fn tab_size(mut self, tab_size: usize) -> Self {
+        self.tab_size = tab_size;
+        self
+    }
+
+    /// Folds away unchanged lines, showing only up to `context` lines
+    /// either side of each change (the default is to show every line).
+    ///
+    /// Each group of changes with its context is put in its own
+    /// `<tbody>`.
+    pub fn context(mut self, context: usize) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Returns a complete HTML page containing the
+    /// [`table()`](struct.HtmlDiff.html#method.table) of differences, a
+    /// style sheet for it, and a legend.
+    pub fn file(
+        &self,
+        a: &[&str],
+        b: &[&str],
+        from_desc: &str,
+        to_desc: &str,
+    ) -> String {
+        format!(
+            "{}{}{}",
+            FILE_HEAD,
+            self.table(a, b, from_desc, to_desc),
+            FILE_TAIL
+        )
+    }
+
+    /// Returns an HTML `<table>` of class `diff` showing lines `a` on the
+    /// left and lines `b` on the right, with headings `from_desc` and
+    /// `to_desc` (which are escaped, so should be plain text).
+    ///
+    /// Line endings are ignored, and the text is escaped, with spaces
+    /// converted to `&nbsp;` so that indentation is preserved.
+    pub fn table(
+        &self,
+        a: &[&str],
+        b: &[&str],
+        from_desc: &str,
+        to_desc: &str,
+    ) -> String {
+        let a =
+            a.iter().map(|line| self.expand(line)).collect::<Vec<_>>();
+        let b =
+            b.iter().map(|line| self.expand(line)).collect::<Vec<_>>();
+        let spans = Differ::new(&a, &b).spans();
+        let mut table = String::from("<table class=\"diff\">\n");
+        table.push_str(&format!(
+            "<thead><tr><th colspan=\"2\">{}</th>\
+             <th colspan=\"2\">{}</th></tr></thead>\n",
+            escape(from_desc),
+            escape(to_desc)
+        ));
+        let groups = match self.context {
+            Some(context) => hunks_for_spans(&spans, context)
+                .into_iter()
+                .map(|hunk| hunk.spans)
+                .collect(),
+            None => vec![spans],
+        };
+        if groups.iter().all(|spans| spans.is_empty()) {
+            table.push_str(
+                "<tbody><tr><td colspan=\"4\">No Differences Found\
+                 </td></tr></tbody>\n",
+            );
+        }
+        for spans in groups.iter().filter(|spans| !spans.is_empty()) {
+            table.push_str("<tbody>\n");
+            for span in spans {
+                push_rows(&mut table, &a, &b, span);
+            }
+            table.push_str("</tbody>\n");
+        }
+        table.push_str("</table>\n");
+        table
+    }
+
+    // Returns the line without its line ending and with its tabs
+    // expanded to spaces.
+    fn expand(&self, line: &str) -> Vec<char> {
+        let mut chars = vec![];
+        for c in line.trim_end_matches(&['\n', '\r'][..]).chars() {
+            if c == '\t' && self.tab_size > 0 {
+                let spaces = self.tab_size - chars.len() % self.tab_size;
+                chars.extend((0..spaces).map(|_| ' '));
+            } else {
+                chars.push(c);
+            }
+        }
+        chars
+    }
+}
+
+fn push_rows(
+    table: &mut String,
+    a: &[Vec<char>],
+    b: &[Vec<char>],
+    span: &Span,
+) {
+    let a_len = span.a_end - span.a_start;
+    let b_len = span.b_end - span.b_start;
+    for row in 0..a_len.max(b_len) {
+        let i = span.a_start + row;
+        let j = span.b_start + row;
+        let (a_cell, b_cell) = match span.tag {
+            Tag::Equal => {
+                (Some(escape_chars(&a[i])), Some(escape_chars(&b[j])))
+            }
+            Tag::Replace if row < a_len && row < b_len => {
+                let (a_cell, b_cell) = highlight(&a[i], &b[j]);
+                (Some(a_cell), Some(b_cell))
+            }
+            _ => (
+                (row < a_len).then(|| marked(&a[i], "diff_sub")),
+                (row < b_len).then(|| marked(&b[j], "diff_add")),
+            ),
+        };
+        table.push_str("<tr>");
+        push_cells(table, a_cell.map(|cell| (i + 1, cell)));
+        push_cells(table, b_cell.map(|cell| (j + 1, cell)));
+        table.push_str("</tr>\n");
+    }
+}
+
+fn push_cells(table: &mut String, cell: Option<(usize, String)>) {
+    match cell {
+        Some((number, text)) => table.push_str(&format!(
+            "<td class=\"diff_header\">{}</td>\
+             <td nowrap=\"nowrap\">{}</td>",
+            number, text
+        )),
+        None => table.push_str(
+            "<td class=\"diff_header\"></td><td nowrap=\"nowrap\"></td>",
+        ),
+    }
+}
+
+// Returns the two lines marked up to show the changes between them.
+fn highlight(a: &[char], b: &[char]) -> (String, String) {
+    let mut a_cell = String::new();
+    let mut b_cell = String::new();
+    for span in Differ::new(a, b).spans() {
+        let a_text = &a[span.a_start..span.a_end];
+        let b_text = &b[span.b_start..span.b_end];
+        match span.tag {
+            Tag::Equal => {
+                a_cell.push_str(&escape_chars(a_text));
+                b_cell.push_str(&escape_chars(b_text));
+            }
+            Tag::Insert => b_cell.push_str(&marked(b_text, "diff_add")),
+            Tag::Delete => a_cell.push_str(&marked(a_text, "diff_sub")),
+            Tag::Replace => {
+                a_cell.push_str(&marked(a_text, "diff_chg"));
+                b_cell.push_str(&marked(b_text, "diff_chg"));
+            }
+        }
+    }
+    (a_cell, b_cell)
+}
+
+fn marked(text: &[char], class: &str) -> String {
+    format!("<span class=\"{}\">{}</span>", class, escape_chars(text))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        push_escaped(&mut escaped, c);
+    }
+    escaped
+}
+
+// Like escape() but also converts spaces to &nbsp; to preserve them.
+fn escape_chars(text: &[char]) -> String {
+    let mut escaped = String::new();
+    for c in text {
+        if *c == ' ' {
+            escaped.push_str("&nbsp;");
+        } else {
+            push_escaped(&mut escaped, *c);
+        }
+    }
+    escaped
+}
+
+fn push_escaped(escaped: &mut String, c: char) {
+    match c {
+        '&' => escaped.push_str("&amp;"),
+        '<' => escaped.push_str("&lt;"),
+        '>' => escaped.push_str("&gt;"),
+        '"' => escaped.push_str("&quot;"),
+        _ => escaped.push(c),
+    }
+}
+
+const FILE_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Differences</title>
+<style>
+table.diff {font-family: monospace; border: medium;}
+.diff_header {background-color: #e0e0e0; text-align: right;}
+.diff_add {background-color: #aaffaa;}
+.diff_chg {background-color: #ffff77;}
+.diff_sub {background-color: #ffaaaa;}
+</style>
+</head>
+<body>
+"#;
+
+const FILE_TAIL: &str = r#"<p>Legend: <span class="diff_add">Added</span>
+<span class="diff_chg">Changed</span>
+<span class="diff_sub">Deleted</span></p>
+</body>
+</html>
+"#;
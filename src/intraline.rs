@@ -0,0 +1,147 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::differ::Differ;
+use crate::structs::{Granularity, LinePair, Span, Tag};
+
+/// Returns the changes within each pair of similar lines in the
+/// `Tag::Replace` spans of lines `a` and `b`.
+///
+/// Within each replaced block, each line in `a` is paired with the most
+/// similar following line in `b` (so pairs are always in order), so long
+/// as their [`ratio()`](struct.Differ.html#method.ratio) (measured at the
+/// given `granularity`) is at least `cutoff`; lines that have no such
+/// partner are unrelated, i.e., simply deleted or inserted. A typical
+/// `cutoff` is `0.6`.
+///
+/// The spans in each [`LinePair`](struct.LinePair.html) are byte offsets
+/// into the pair's lines and cover both lines completely.
+///
+/// ```
+/// use differ::{intraline_spans, Differ, Granularity, Span};
+///
+/// let a = ["let x = 1;", "// old note"];
+/// let b = ["let y = 1;", "fn main() {}"];
+/// let spans = Differ::new(&a, &b).spans();
+/// let pairs = intraline_spans(&a, &b, &spans, Granularity::Word, 0.6);
+/// assert_eq!(pairs.len(), 1); // the comment and main() are unrelated
+/// assert_eq!((pairs[0].a_line, pairs[0].b_line), (0, 0));
+/// assert_eq!(pairs[0].spans[1], Span::replace(4, 5, 4, 5)); // x -> y
+/// ```
+pub fn intraline_spans(
+    a: &[&str],
+    b: &[&str],
+    spans: &[Span],
+    granularity: Granularity,
+    cutoff: f64,
+) -> Vec<LinePair> {
+    let mut pairs = vec![];
+    for span in spans.iter().filter(|span| span.tag == Tag::Replace) {
+        let b_tokens = (span.b_start..span.b_end)
+            .map(|j| tokenize(b[j], granularity))
+            .collect::<Vec<_>>();
+        let mut next_j = span.b_start;
+        for (i, a_line) in
+            a.iter().enumerate().take(span.a_end).skip(span.a_start)
+        {
+            let a_tokens = tokenize(a_line, granularity);
+            let mut best = None;
+            let mut best_ratio = 0.0;
+            for j in next_j..span.b_end {
+                let b_tokens = &b_tokens[j - span.b_start];
+                let differ = Differ::new(&a_tokens.1, &b_tokens.1);
+                let threshold = cutoff.max(best_ratio);
+                if differ.real_quick_ratio() >= threshold
+                    && differ.quick_ratio() >= threshold
+                {
+                    let ratio = differ.ratio();
+                    if ratio >= cutoff && ratio > best_ratio {
+                        best = Some((j, differ.spans()));
+                        best_ratio = ratio;
+                    }
+                }
+            }
+            if let Some((j, token_spans)) = best {
+                let b_tokens = &b_tokens[j - span.b_start];
+                pairs.push(LinePair {
+                    a_line: i,
+                    b_line: j,
+                    spans: token_spans
+                        .iter()
+                        .map(|s| Span {
+                            tag: s.tag.clone(),
+                            a_start: a_tokens.0[s.a_start],
+                            a_end: a_tokens.0[s.a_end],
+                            b_start: b_tokens.0[s.b_start],
+                            b_end: b_tokens.0[s.b_end],
+                        })
+                        .collect(),
+                });
+                next_j = j + 1;
+            }
+        }
+    }
+    pairs
+}
+
+// Returns the byte offset of each token (plus a final offset of the
+// line's length) and the tokens themselves.
+fn tokenize(
+    line: &str,
+    granularity: Granularity,
+) -> (Vec<usize>, Vec<&str>) {
+    let tokens = match granularity {
+        Granularity::Char => line
+            .char_indices()
+            .map(|(i, c)| &line[i..i + c.len_utf8()])
+            .collect::<Vec<_>>(),
+        Granularity::Word => words(line),
+    };
+    let mut offsets = Vec::with_capacity(tokens.len() + 1);
+    let mut offset = 0;
+    for token in &tokens {
+        offsets.push(offset);
+        offset += token.len();
+    }
+    offsets.push(offset);
+    (offsets, tokens)
+}
+
+/// Returns the text split into words (runs of alphanumeric characters or
+/// underscores), runs of whitespace, and single punctuation characters;
+/// concatenating the words gives back the text.
+pub(crate) fn words(text: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut start = 0;
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        let kind = char_kind(c);
+        if let Some(previous) = previous {
+            if kind != previous || kind == CharKind::Punctuation {
+                words.push(&text[start..i]);
+                start = i;
+            }
+        }
+        previous = Some(kind);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharKind {
+    Word,
+    Space,
+    Punctuation,
+}
+
+fn char_kind(c: char) -> CharKind {
+    if c.is_alphanumeric() || c == '_' {
+        CharKind::Word
+    } else if c.is_whitespace() {
+        CharKind::Space
+    } else {
+        CharKind::Punctuation
+    }
+}
@@ -18,6 +18,9 @@ and replacements.
 
 `differ` is based on the Python
 [`difflib.SequenceMatcher`](https://docs.python.org/3/library/difflib.html#difflib.SequenceMatcher).
+Other matching algorithms (Myers' minimal diff, patience diff, and
+histogram diff) can be chosen using
+[`Differ::algorithm()`](struct.Differ.html#method.algorithm).
 
 # Dependencies
 
@@ -128,17 +131,28 @@ let differ = Differ::new(&a, &b);
 for span in differ.spans() {
     match span.tag {
         Tag::Equal => (), // ignore
-        Tag::Insert => println!("{:?}", span),
-        Tag::Delete => println!("{:?}", span),
-        Tag::Replace => println!("{:?}", span),
+        Tag::Insert => print_items('+', &b[span.b_start..span.b_end]),
+        Tag::Delete => print_items('-', &a[span.a_start..span.a_end]),
+        Tag::Replace => {
+            print_items('-', &a[span.a_start..span.a_end]);
+            print_items('+', &b[span.b_start..span.b_end]);
+        }
+    }
+}
+
+fn print_items(c: char, items: &[Item]) {
+    for item in items {
+        println!("{} {} at ({}, {})", c, item.text, item.x, item.y);
     }
 }
 ```
 
 Output:
 ```text
-Span { tag: Delete, a_start: 0, a_end: 1, b_start: 0, b_end: 0 }
-Span { tag: Replace, a_start: 3, a_end: 4, b_start: 2, b_end: 4 }
+- quebec at (1, 3)
+- x-ray at (5, 9)
++ yankee at (9, 5)
++ charlie at (8, 3)
 ```
 
 # Upgrading
@@ -153,10 +167,55 @@ Differ is free open source software (FOSS) licensed under the GNU
 General Public License version 3 (GPLv3).
 */
 
+mod cleanup;
 mod differ;
+mod distance;
+mod gnu;
+mod histogram;
+mod html;
+mod intraline;
+mod merge;
+mod moves;
+mod myers;
+mod ndiff;
+mod patch;
+mod patience;
+mod position;
+mod slider;
 mod structs;
 #[cfg(test)]
 mod tests;
-
-pub use crate::differ::{Differ, spans_for_matches};
-pub use crate::structs::{Match, Span, Tag};
+mod transform;
+mod unidiff;
+mod worddiff;
+
+pub use crate::cleanup::{
+    cleanup_efficiency, cleanup_semantic, cleanup_semantic_lossless,
+};
+pub use crate::differ::{
+    close_matches, context_diff, context_diff_for_hunks, hunks_for_spans,
+    spans_for_matches, unified_diff, unified_diff_for_hunks, Differ,
+};
+pub use crate::distance::{
+    damerau_levenshtein, levenshtein, EditDistance,
+};
+pub use crate::html::HtmlDiff;
+pub use crate::intraline::intraline_spans;
+pub use crate::merge::{
+    merge3, merge3_for_matches, merge3_text, merge3_text_for_regions,
+};
+pub use crate::moves::detect_moves;
+pub use crate::ndiff::ndiff;
+pub use crate::patch::{
+    apply_hunks, Edit, HunkResult, Patch, PatchError, PatchHunk,
+    PatchLine,
+};
+pub use crate::position::PositionMap;
+pub use crate::slider::slide_spans;
+pub use crate::structs::{
+    Algorithm, EditCosts, Granularity, Hunk, LinePair, Match,
+    MergeRegion, MergeTag, Move, Snap, Span, Tag, WordDiffStyle,
+};
+pub use crate::transform::{compose_spans, invert_spans};
+pub use crate::unidiff::{parse_unified_diff, FilePatch, ParseError};
+pub use crate::worddiff::{word_diff, word_spans};
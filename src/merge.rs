@@ -0,0 +1,271 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::differ::Differ;
+use crate::structs::{Match, MergeRegion, MergeTag};
+use std::hash::Hash;
+
+/// Returns the regions of a three-way merge of the changes made to
+/// `base` by `ours` and by `theirs`; this is the equivalent of `diff3
+/// -m`'s merge.
+///
+/// Regions where only one side changed the base, or where both sides
+/// made the same change, are resolved automatically; regions where the
+/// sides made different changes are tagged `MergeTag::Conflict`. Use
+/// [`merge3_text_for_regions()`](fn.merge3_text_for_regions.html) to
+/// get the merged text.
+///
+/// ```
+/// use differ::{merge3, MergeTag};
+///
+/// let base = ["a", "b", "c", "d"];
+/// let ours = ["a", "B", "c", "d"];
+/// let theirs = ["a", "b", "c", "D"];
+/// let tags = merge3(&base, &ours, &theirs)
+///     .iter()
+///     .map(|region| region.tag)
+///     .collect::<Vec<_>>();
+/// assert_eq!(
+///     tags,
+///     vec![
+///         MergeTag::Unchanged,
+///         MergeTag::Ours,
+///         MergeTag::Unchanged,
+///         MergeTag::Theirs
+///     ]
+/// );
+/// ```
+pub fn merge3<T>(base: &[T], ours: &[T], theirs: &[T]) -> Vec<MergeRegion>
+where
+    T: Hash + Eq,
+{
+    let ours_matches = Differ::new(base, ours).matches();
+    let theirs_matches = Differ::new(base, theirs).matches();
+    merge3_for_matches(base, ours, theirs, &ours_matches, &theirs_matches)
+}
+
+/// Returns the regions of a three-way merge given the
+/// [`matches()`](struct.Differ.html#method.matches) of `base` with
+/// `ours` and of `base` with `theirs`.
+///
+/// This makes it possible to use a different
+/// [`Algorithm`](enum.Algorithm.html) or other settings for the
+/// matching. See [`merge3()`](fn.merge3.html) for details.
+pub fn merge3_for_matches<T>(
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+    ours_matches: &[Match],
+    theirs_matches: &[Match],
+) -> Vec<MergeRegion>
+where
+    T: Eq,
+{
+    let mut regions = vec![];
+    let mut base_start = 0;
+    let mut ours_start = 0;
+    let mut theirs_start = 0;
+    for sync in
+        sync_regions(base, ours, theirs, ours_matches, theirs_matches)
+    {
+        let ours_changed = ours[ours_start..sync.ours_start]
+            != base[base_start..sync.base_start];
+        let theirs_changed = theirs[theirs_start..sync.theirs_start]
+            != base[base_start..sync.base_start];
+        let tag = if !ours_changed && !theirs_changed {
+            None
+        } else if ours[ours_start..sync.ours_start]
+            == theirs[theirs_start..sync.theirs_start]
+        {
+            Some(MergeTag::Both)
+        } else if !theirs_changed {
+            Some(MergeTag::Ours)
+        } else if !ours_changed {
+            Some(MergeTag::Theirs)
+        } else {
+            Some(MergeTag::Conflict)
+        };
+        if let Some(tag) = tag {
+            regions.push(MergeRegion {
+                tag,
+                base_start,
+                base_end: sync.base_start,
+                ours_start,
+                ours_end: sync.ours_start,
+                theirs_start,
+                theirs_end: sync.theirs_start,
+            });
+        }
+        if sync.base_start < sync.base_end {
+            regions.push(sync.clone());
+        }
+        base_start = sync.base_end;
+        ours_start = sync.ours_end;
+        theirs_start = sync.theirs_end;
+    }
+    regions
+}
+
+// Returns the regions of the base that both ours and theirs match, plus
+// a final empty region at the end of all three sequences.
+fn sync_regions<T>(
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+    ours_matches: &[Match],
+    theirs_matches: &[Match],
+) -> Vec<MergeRegion> {
+    let mut regions = vec![];
+    let mut i = 0;
+    let mut j = 0;
+    while i < ours_matches.len() && j < theirs_matches.len() {
+        let o = &ours_matches[i];
+        let t = &theirs_matches[j];
+        let start = o.a_start.max(t.a_start);
+        let end = (o.a_start + o.length).min(t.a_start + t.length);
+        if start < end {
+            let ours_start = o.b_start + start - o.a_start;
+            let theirs_start = t.b_start + start - t.a_start;
+            regions.push(MergeRegion {
+                tag: MergeTag::Unchanged,
+                base_start: start,
+                base_end: end,
+                ours_start,
+                ours_end: ours_start + end - start,
+                theirs_start,
+                theirs_end: theirs_start + end - start,
+            });
+        }
+        if o.a_start + o.length < t.a_start + t.length {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    regions.push(MergeRegion {
+        tag: MergeTag::Unchanged,
+        base_start: base.len(),
+        base_end: base.len(),
+        ours_start: ours.len(),
+        ours_end: ours.len(),
+        theirs_start: theirs.len(),
+        theirs_end: theirs.len(),
+    });
+    regions
+}
+
+/// Returns the three-way merge of texts `base`, `ours`, and `theirs`
+/// compared line by line, and the number of conflicts.
+///
+/// Each conflict is shown using git's `diff3` conflict style:
+///
+/// ```text
+/// <<<<<<< ours_label
+/// ours' lines
+/// ||||||| base_label
+/// the base's lines
+/// =======
+/// theirs' lines
+/// >>>>>>> theirs_label
+/// ```
+///
+/// ```
+/// use differ::merge3_text;
+///
+/// let base = "host = a\nuser = me\nport = 80\n";
+/// let ours = "host = b\nuser = me\nport = 80\n";
+/// let theirs = "host = c\nuser = me\nport = 8080\n";
+/// let (text, conflicts) =
+///     merge3_text(base, ours, theirs, "ours", "base", "theirs");
+/// assert_eq!(conflicts, 1);
+/// assert_eq!(
+///     text,
+///     "<<<<<<< ours\nhost = b\n||||||| base\nhost = a\n=======\n\
+///      host = c\n>>>>>>> theirs\nuser = me\nport = 8080\n"
+/// );
+/// ```
+///
+/// To control the matching, split the texts into lines (keeping their
+/// line endings, e.g., using `split_inclusive()`), get the regions
+/// using [`merge3_for_matches()`](fn.merge3_for_matches.html), and use
+/// [`merge3_text_for_regions()`](fn.merge3_text_for_regions.html).
+pub fn merge3_text(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    ours_label: &str,
+    base_label: &str,
+    theirs_label: &str,
+) -> (String, usize) {
+    let base_lines = base.split_inclusive('\n').collect::<Vec<_>>();
+    let ours_lines = ours.split_inclusive('\n').collect::<Vec<_>>();
+    let theirs_lines = theirs.split_inclusive('\n').collect::<Vec<_>>();
+    let regions = merge3(&base_lines, &ours_lines, &theirs_lines);
+    let conflicts = regions
+        .iter()
+        .filter(|region| region.tag == MergeTag::Conflict)
+        .count();
+    let text = merge3_text_for_regions(
+        &base_lines,
+        &ours_lines,
+        &theirs_lines,
+        &regions,
+        ours_label,
+        base_label,
+        theirs_label,
+    );
+    (text, conflicts)
+}
+
+/// Returns the merged text for the given
+/// [`MergeRegion`](struct.MergeRegion.html)s of lines `base`, `ours`,
+/// and `theirs`.
+///
+/// The lines should keep their line endings. If a line that is followed
+/// by a conflict marker has no line ending, one is added. See
+/// [`merge3_text()`](fn.merge3_text.html) for details.
+pub fn merge3_text_for_regions(
+    base: &[&str],
+    ours: &[&str],
+    theirs: &[&str],
+    regions: &[MergeRegion],
+    ours_label: &str,
+    base_label: &str,
+    theirs_label: &str,
+) -> String {
+    let mut text = String::new();
+    for region in regions {
+        let ours_lines = &ours[region.ours_start..region.ours_end];
+        let theirs_lines =
+            &theirs[region.theirs_start..region.theirs_end];
+        match region.tag {
+            MergeTag::Unchanged | MergeTag::Ours | MergeTag::Both => {
+                text.push_str(&ours_lines.concat())
+            }
+            MergeTag::Theirs => text.push_str(&theirs_lines.concat()),
+            MergeTag::Conflict => {
+                push_marker(&mut text, "<<<<<<<", ours_label);
+                text.push_str(&ours_lines.concat());
+                push_marker(&mut text, "|||||||", base_label);
+                text.push_str(
+                    &base[region.base_start..region.base_end].concat(),
+                );
+                push_marker(&mut text, "=======", "");
+                text.push_str(&theirs_lines.concat());
+                push_marker(&mut text, ">>>>>>>", theirs_label);
+            }
+        }
+    }
+    text
+}
+
+fn push_marker(text: &mut String, marker: &str, label: &str) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(marker);
+    if !label.is_empty() {
+        text.push(' ');
+        text.push_str(label);
+    }
+    text.push('\n');
+}
@@ -0,0 +1,112 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::differ::Differ;
+use crate::structs::{Move, Span, Tag};
+use std::hash::Hash;
+
+/// Returns the blocks that were moved, given the spans that convert `a`
+/// into `b` (e.g., as returned by
+/// [`spans()`](struct.Differ.html#method.spans)).
+///
+/// The spans report a moved block as an unrelated deletion and
+/// insertion. This pairs up the deleted blocks (the `a` side of
+/// `Tag::Delete` and `Tag::Replace` spans) with the inserted blocks (the
+/// `b` side of `Tag::Insert` and `Tag::Replace` spans) that are at least
+/// `min_len` items long and whose
+/// [`ratio()`](struct.Differ.html#method.ratio) is at least `cutoff`;
+/// use a `cutoff` of `1.0` to find only blocks that were moved
+/// unchanged. The most similar pairs are paired first, each block is in
+/// at most one move, and the two sides of a single `Tag::Replace` span
+/// are never paired. The moves are returned in `a` order.
+///
+/// ```
+/// use differ::{detect_moves, Differ, Move};
+///
+/// let a = ["fn a() {", "}", "", "fn b() {", "}", "fn c() {", "}"];
+/// let b = ["fn b() {", "}", "fn c() {", "}", "fn a() {", "}", ""];
+/// let spans = Differ::new(&a, &b).spans();
+/// let moves = detect_moves(&a, &b, &spans, 1.0, 2);
+/// assert_eq!(
+///     moves,
+///     vec![Move {
+///         a_start: 0,
+///         a_end: 3,
+///         b_start: 4,
+///         b_end: 7,
+///         ratio: 1.0
+///     }]
+/// );
+/// ```
+pub fn detect_moves<T>(
+    a: &[T],
+    b: &[T],
+    spans: &[Span],
+    cutoff: f64,
+    min_len: usize,
+) -> Vec<Move>
+where
+    T: Hash + Eq,
+{
+    let min_len = min_len.max(1);
+    let deleted = spans
+        .iter()
+        .enumerate()
+        .filter(|(_, span)| {
+            span.tag != Tag::Equal
+                && span.tag != Tag::Insert
+                && span.a_end - span.a_start >= min_len
+        })
+        .collect::<Vec<_>>();
+    let inserted = spans
+        .iter()
+        .enumerate()
+        .filter(|(_, span)| {
+            span.tag != Tag::Equal
+                && span.tag != Tag::Delete
+                && span.b_end - span.b_start >= min_len
+        })
+        .collect::<Vec<_>>();
+    let mut candidates = vec![];
+    for (d, (i, from)) in deleted.iter().enumerate() {
+        let a_block = &a[from.a_start..from.a_end];
+        for (n, (j, to)) in inserted.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let b_block = &b[to.b_start..to.b_end];
+            let differ = Differ::new(a_block, b_block);
+            if differ.real_quick_ratio() >= cutoff
+                && differ.quick_ratio() >= cutoff
+            {
+                let ratio = differ.ratio();
+                if ratio >= cutoff {
+                    candidates.push((ratio, d, n));
+                }
+            }
+        }
+    }
+    // Most similar first; ties in a then b order.
+    candidates.sort_by(|x, y| {
+        y.0.partial_cmp(&x.0).unwrap().then((x.1, x.2).cmp(&(y.1, y.2)))
+    });
+    let mut deleted_used = vec![false; deleted.len()];
+    let mut inserted_used = vec![false; inserted.len()];
+    let mut moves = vec![];
+    for (ratio, d, n) in candidates {
+        if deleted_used[d] || inserted_used[n] {
+            continue;
+        }
+        deleted_used[d] = true;
+        inserted_used[n] = true;
+        let (from, to) = (deleted[d].1, inserted[n].1);
+        moves.push(Move {
+            a_start: from.a_start,
+            a_end: from.a_end,
+            b_start: to.b_start,
+            b_end: to.b_end,
+            ratio,
+        });
+    }
+    moves.sort_by_key(|m| m.a_start);
+    moves
+}
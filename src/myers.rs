@@ -0,0 +1,207 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::structs::Match;
+use std::ops::{Index, IndexMut};
+
+/// Returns the matches between `a` and `b` using Myers' O(ND) algorithm
+/// in its linear space (middle snake) form.
+///
+/// The matches are unsorted and may be adjacent: the caller is expected
+/// to sort and coalesce them.
+pub(crate) fn matches<T: Eq>(a: &[T], b: &[T]) -> Vec<Match> {
+    matches_in(a, 0, a.len(), b, 0, b.len())
+}
+
+/// Returns the matches between `a[a_start..a_end]` and
+/// `b[b_start..b_end]` using Myers' algorithm; used by the other
+/// algorithms as a fallback for regions they cannot anchor.
+pub(crate) fn matches_in<T: Eq>(
+    a: &[T],
+    a_start: usize,
+    a_end: usize,
+    b: &[T],
+    b_start: usize,
+    b_end: usize,
+) -> Vec<Match> {
+    let max = (a_end - a_start) + (b_end - b_start) + 1;
+    let mut vf = V::new(max);
+    let mut vb = V::new(max);
+    let mut matches = vec![];
+    conquer(
+        a,
+        a_start,
+        a_end,
+        b,
+        b_start,
+        b_end,
+        &mut vf,
+        &mut vb,
+        &mut matches,
+    );
+    matches
+}
+
+#[allow(clippy::too_many_arguments)]
+fn conquer<T: Eq>(
+    a: &[T],
+    mut a_start: usize,
+    mut a_end: usize,
+    b: &[T],
+    mut b_start: usize,
+    mut b_end: usize,
+    vf: &mut V,
+    vb: &mut V,
+    matches: &mut Vec<Match>,
+) {
+    let prefix = common_prefix(a, a_start, a_end, b, b_start, b_end);
+    if prefix > 0 {
+        matches.push(Match::new(a_start, b_start, prefix));
+        a_start += prefix;
+        b_start += prefix;
+    }
+    let suffix = common_suffix(a, a_start, a_end, b, b_start, b_end);
+    if suffix > 0 {
+        a_end -= suffix;
+        b_end -= suffix;
+        matches.push(Match::new(a_end, b_end, suffix));
+    }
+    if a_start < a_end && b_start < b_end {
+        let (x, y) =
+            middle_snake(a, a_start, a_end, b, b_start, b_end, vf, vb);
+        conquer(a, a_start, x, b, b_start, y, vf, vb, matches);
+        conquer(a, x, a_end, b, y, b_end, vf, vb, matches);
+    }
+}
+
+// Returns a point on an optimal edit path through the given region;
+// the region must be non-empty on both sides and have no common prefix
+// or suffix, which guarantees that the point splits it.
+#[allow(clippy::too_many_arguments)]
+fn middle_snake<T: Eq>(
+    a: &[T],
+    a_start: usize,
+    a_end: usize,
+    b: &[T],
+    b_start: usize,
+    b_end: usize,
+    vf: &mut V,
+    vb: &mut V,
+) -> (usize, usize) {
+    let n = (a_end - a_start) as isize;
+    let m = (b_end - b_start) as isize;
+    let delta = n - m;
+    let odd = delta & 1 == 1;
+    vf[1] = 0;
+    vb[1] = 0;
+    let d_max = (n + m + 1) / 2 + 1;
+    for d in 0..d_max {
+        let mut k = d;
+        while k >= -d {
+            let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n
+                && y < m
+                && a[a_start + x as usize] == b[b_start + y as usize]
+            {
+                x += 1;
+                y += 1;
+            }
+            vf[k] = x;
+            if odd && (k - delta).abs() < d && vf[k] + vb[delta - k] >= n
+            {
+                return (a_start + x0 as usize, b_start + y0 as usize);
+            }
+            k -= 2;
+        }
+        let mut k = d;
+        while k >= -d {
+            let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n
+                && y < m
+                && a[a_end - 1 - x as usize] == b[b_end - 1 - y as usize]
+            {
+                x += 1;
+                y += 1;
+            }
+            vb[k] = x;
+            if !odd
+                && (k - delta).abs() <= d
+                && vb[k] + vf[delta - k] >= n
+            {
+                return (
+                    a_start + (n - x) as usize,
+                    b_start + (m - y) as usize,
+                );
+            }
+            k -= 2;
+        }
+    }
+    unreachable!("no middle snake found")
+}
+
+pub(crate) fn common_prefix<T: Eq>(
+    a: &[T],
+    a_start: usize,
+    a_end: usize,
+    b: &[T],
+    b_start: usize,
+    b_end: usize,
+) -> usize {
+    a[a_start..a_end]
+        .iter()
+        .zip(&b[b_start..b_end])
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+pub(crate) fn common_suffix<T: Eq>(
+    a: &[T],
+    a_start: usize,
+    a_end: usize,
+    b: &[T],
+    b_start: usize,
+    b_end: usize,
+) -> usize {
+    a[a_start..a_end]
+        .iter()
+        .rev()
+        .zip(b[b_start..b_end].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+// The furthest reaching x for each diagonal k, indexable by negative k.
+struct V {
+    offset: isize,
+    v: Vec<isize>,
+}
+
+impl V {
+    fn new(max: usize) -> V {
+        V { offset: max as isize + 1, v: vec![0; 2 * max + 3] }
+    }
+}
+
+impl Index<isize> for V {
+    type Output = isize;
+
+    fn index(&self, k: isize) -> &isize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut isize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::differ::Differ;
+use crate::structs::Tag;
+
+/// Returns a human-readable line-by-line diff of lines `a` and `b`; this
+/// is the equivalent of difflib's `ndiff()` (and `Differ.compare()`).
+///
+/// Each line of the result begins with a two character code:
+///
+/// - `"- "` the line is unique to `a`
+/// - `"+ "` the line is unique to `b`
+/// - `"  "` the line is common to both
+/// - `"? "` the line is a guide to the changes within the line above,
+///   marking deleted (`-`), inserted (`+`), and replaced (`^`)
+///   characters
+///
+/// When a block of lines is replaced, the most similar pair of lines in
+/// the block is compared character by character (ignoring spaces and
+/// tabs as anchors) to produce the `?` guide lines, and the lines either
+/// side of the pair are handled in the same way.
+///
+/// The lines should keep their line endings (e.g., be split using
+/// `split_inclusive('\n')`), and so will the result's lines, so that
+/// they can simply be concatenated.
+///
+/// ```
+/// use differ::ndiff;
+///
+/// let a = ["one\n", "two\n", "three\n"];
+/// let b = ["ore\n", "tree\n", "emu\n"];
+/// assert_eq!(
+///     ndiff(&a, &b).concat(),
+///     "- one\n?  ^\n+ ore\n?  ^\n- two\n- three\n?  -\n+ tree\n+ emu\n"
+/// );
+/// ```
+pub fn ndiff(a: &[&str], b: &[&str]) -> Vec<String> {
+    let ndiff = Ndiff {
+        a,
+        b,
+        a_chars: a.iter().map(|line| line.chars().collect()).collect(),
+        b_chars: b.iter().map(|line| line.chars().collect()).collect(),
+    };
+    let mut diff = vec![];
+    for span in Differ::new(a, b).spans() {
+        match span.tag {
+            Tag::Equal => {
+                dump(&mut diff, ' ', a, span.a_start, span.a_end)
+            }
+            Tag::Insert => {
+                dump(&mut diff, '+', b, span.b_start, span.b_end)
+            }
+            Tag::Delete => {
+                dump(&mut diff, '-', a, span.a_start, span.a_end)
+            }
+            Tag::Replace => ndiff.fancy_replace(
+                &mut diff,
+                span.a_start,
+                span.a_end,
+                span.b_start,
+                span.b_end,
+            ),
+        }
+    }
+    diff
+}
+
+// Only a pair of lines at least this similar is used to synch up a
+// replaced block.
+const CUTOFF: f64 = 0.75;
+
+struct Ndiff<'a> {
+    a: &'a [&'a str],
+    b: &'a [&'a str],
+    a_chars: Vec<Vec<char>>,
+    b_chars: Vec<Vec<char>>,
+}
+
+impl<'a> Ndiff<'a> {
+    fn fancy_replace(
+        &self,
+        diff: &mut Vec<String>,
+        a_start: usize,
+        a_end: usize,
+        b_start: usize,
+        b_end: usize,
+    ) {
+        // Find the most similar pair that isn't identical; identical
+        // lines are only used if there is no similar pair.
+        let mut best_ratio = 0.74;
+        let mut best = (a_start, b_start);
+        let mut identical = None;
+        for j in b_start..b_end {
+            let mut cruncher = Differ::new(&[], &self.b_chars[j])
+                .junk(is_character_junk);
+            for i in a_start..a_end {
+                if self.a[i] == self.b[j] {
+                    if identical.is_none() {
+                        identical = Some((i, j));
+                    }
+                    continue;
+                }
+                cruncher.set_a(&self.a_chars[i]);
+                if cruncher.real_quick_ratio() > best_ratio
+                    && cruncher.quick_ratio() > best_ratio
+                {
+                    let ratio = cruncher.ratio();
+                    if ratio > best_ratio {
+                        best_ratio = ratio;
+                        best = (i, j);
+                    }
+                }
+            }
+        }
+        if best_ratio < CUTOFF {
+            match identical {
+                Some(pair) => best = pair,
+                None => {
+                    self.plain_replace(
+                        diff, a_start, a_end, b_start, b_end,
+                    );
+                    return;
+                }
+            }
+        } else {
+            identical = None;
+        }
+        let (best_i, best_j) = best;
+        self.fancy_helper(diff, a_start, best_i, b_start, best_j);
+        if identical.is_some() {
+            diff.push(format!("  {}", self.a[best_i]));
+        } else {
+            self.push_with_guides(diff, best_i, best_j);
+        }
+        self.fancy_helper(diff, best_i + 1, a_end, best_j + 1, b_end);
+    }
+
+    fn fancy_helper(
+        &self,
+        diff: &mut Vec<String>,
+        a_start: usize,
+        a_end: usize,
+        b_start: usize,
+        b_end: usize,
+    ) {
+        if a_start < a_end {
+            if b_start < b_end {
+                self.fancy_replace(diff, a_start, a_end, b_start, b_end);
+            } else {
+                dump(diff, '-', self.a, a_start, a_end);
+            }
+        } else if b_start < b_end {
+            dump(diff, '+', self.b, b_start, b_end);
+        }
+    }
+
+    fn plain_replace(
+        &self,
+        diff: &mut Vec<String>,
+        a_start: usize,
+        a_end: usize,
+        b_start: usize,
+        b_end: usize,
+    ) {
+        if b_end - b_start < a_end - a_start {
+            dump(diff, '+', self.b, b_start, b_end);
+            dump(diff, '-', self.a, a_start, a_end);
+        } else {
+            dump(diff, '-', self.a, a_start, a_end);
+            dump(diff, '+', self.b, b_start, b_end);
+        }
+    }
+
+    // Pushes the "- ", "? ", "+ ", "? " lines for a similar pair.
+    fn push_with_guides(
+        &self,
+        diff: &mut Vec<String>,
+        i: usize,
+        j: usize,
+    ) {
+        let a_chars = &self.a_chars[i];
+        let b_chars = &self.b_chars[j];
+        let mut a_tags = String::new();
+        let mut b_tags = String::new();
+        let cruncher =
+            Differ::new(a_chars, b_chars).junk(is_character_junk);
+        for span in cruncher.spans() {
+            let a_len = span.a_end - span.a_start;
+            let b_len = span.b_end - span.b_start;
+            let (a_tag, b_tag) = match span.tag {
+                Tag::Equal => (' ', ' '),
+                Tag::Insert => (' ', '+'),
+                Tag::Delete => ('-', ' '),
+                Tag::Replace => ('^', '^'),
+            };
+            if span.tag != Tag::Insert {
+                a_tags.extend((0..a_len).map(|_| a_tag));
+            }
+            if span.tag != Tag::Delete {
+                b_tags.extend((0..b_len).map(|_| b_tag));
+            }
+        }
+        diff.push(format!("- {}", self.a[i]));
+        push_guide(diff, a_chars, &a_tags);
+        diff.push(format!("+ {}", self.b[j]));
+        push_guide(diff, b_chars, &b_tags);
+    }
+}
+
+// Pushes a "? " guide line unless it has no marks; tabs and other
+// whitespace in the original line are kept so that the marks line up.
+fn push_guide(diff: &mut Vec<String>, chars: &[char], tags: &str) {
+    let mut guide = String::new();
+    for (c, tag) in chars.iter().zip(tags.chars()) {
+        guide.push(if tag == ' ' && c.is_whitespace() {
+            *c
+        } else {
+            tag
+        });
+    }
+    let guide = guide.trim_end();
+    if !guide.is_empty() {
+        diff.push(format!("? {}\n", guide));
+    }
+}
+
+fn dump(
+    diff: &mut Vec<String>,
+    tag: char,
+    lines: &[&str],
+    start: usize,
+    end: usize,
+) {
+    for line in &lines[start..end] {
+        diff.push(format!("{} {}", tag, line));
+    }
+}
+
+fn is_character_junk(c: &char) -> bool {
+    *c == ' ' || *c == '\t'
+}
@@ -0,0 +1,634 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::structs::{Hunk, Span, Tag};
+use crate::transform::{compose_pieces, invert_span, Piece};
+#[cfg(feature="use_serde")]
+use serde_derive::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// Holds everything needed to convert sequence `a` into sequence `b`
+/// without having `b` at hand: the spans that differ, the items they
+/// delete from `a`, and the items they insert from `b`.
+///
+/// A `Patch` is serializable (with the `use_serde` feature) so can be
+/// sent elsewhere and applied there; the deleted items are kept so that
+/// [`apply()`](struct.Patch.html#method.apply) can verify that it is
+/// being applied to the right sequence.
+///
+/// ```
+/// use differ::{Differ, Patch};
+///
+/// let a = ["the", "quick", "brown", "fox"];
+/// let b = ["the", "slow", "brown", "fox", "sleeps"];
+/// let spans = Differ::new(&a, &b).spans();
+/// let patch = Patch::new(&a, &b, &spans);
+/// assert_eq!(patch.apply(&a), Ok(b.to_vec()));
+/// assert!(patch.apply(&["the", "lazy", "brown", "fox"]).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct Patch<T> {
+    pub a_len: usize,
+    pub b_len: usize,
+    pub edits: Vec<Edit<T>>,
+}
+
+/// Holds one non-`Tag::Equal` span of a [`Patch`](struct.Patch.html)
+/// with the items it deletes from `a` (empty for a `Tag::Insert`) and
+/// inserts from `b` (empty for a `Tag::Delete`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct Edit<T> {
+    pub span: Span,
+    pub deleted: Vec<T>,
+    pub inserted: Vec<T>,
+}
+
+impl<T> Patch<T>
+where
+    T: Clone + PartialEq,
+{
+    /// Creates a new `Patch` from the spans that convert `a` into `b`
+    /// (e.g., as returned by
+    /// [`spans()`](struct.Differ.html#method.spans)).
+    pub fn new(a: &[T], b: &[T], spans: &[Span]) -> Self {
+        let edits = spans
+            .iter()
+            .filter(|span| span.tag != Tag::Equal)
+            .map(|span| Edit {
+                span: span.clone(),
+                deleted: a[span.a_start..span.a_end].to_vec(),
+                inserted: b[span.b_start..span.b_end].to_vec(),
+            })
+            .collect();
+        Patch { a_len: a.len(), b_len: b.len(), edits }
+    }
+
+    /// Returns the patch that converts `b` back into `a`, i.e., that
+    /// undoes this patch.
+    ///
+    /// ```
+    /// use differ::{Differ, Patch};
+    ///
+    /// let a = ["a", "b", "c"];
+    /// let b = ["a", "B", "c", "d"];
+    /// let patch = Patch::new(&a, &b, &Differ::new(&a, &b).spans());
+    /// let b = patch.apply(&a).unwrap();
+    /// assert_eq!(patch.invert().apply(&b), Ok(a.to_vec()));
+    /// ```
+    pub fn invert(&self) -> Self {
+        Patch {
+            a_len: self.b_len,
+            b_len: self.a_len,
+            edits: self
+                .edits
+                .iter()
+                .map(|edit| Edit {
+                    span: invert_span(&edit.span),
+                    deleted: edit.inserted.clone(),
+                    inserted: edit.deleted.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns `true` if the patch makes no changes.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Returns sequence `b` produced by applying the patch to sequence
+    /// `a`.
+    ///
+    /// Returns an error if `a` is not the sequence the patch was made
+    /// from, i.e., if it has a different length or any of the items to
+    /// be deleted or replaced differ, or if the patch itself is
+    /// malformed.
+    pub fn apply(&self, a: &[T]) -> Result<Vec<T>, PatchError> {
+        if a.len() != self.a_len {
+            return Err(PatchError::WrongLength {
+                expected: self.a_len,
+                actual: a.len(),
+            });
+        }
+        if !self.is_well_formed() {
+            return Err(PatchError::Malformed);
+        }
+        let mut b = Vec::with_capacity(self.b_len);
+        let mut i = 0;
+        for edit in &self.edits {
+            let span = &edit.span;
+            if a[span.a_start..span.a_end] != edit.deleted[..] {
+                return Err(PatchError::Mismatch {
+                    a_start: span.a_start,
+                });
+            }
+            b.extend_from_slice(&a[i..span.a_start]);
+            b.extend_from_slice(&edit.inserted);
+            i = span.a_end;
+        }
+        b.extend_from_slice(&a[i..]);
+        Ok(b)
+    }
+
+    /// Returns all the spans (equals, insertions, deletions,
+    /// replacements) necessary to convert sequence `a` into `b`.
+    pub fn spans(&self) -> Vec<Span> {
+        let mut spans = vec![];
+        let (mut i, mut j) = (0, 0);
+        for edit in &self.edits {
+            let span = &edit.span;
+            if i < span.a_start {
+                spans.push(Span::equal(i, span.a_start, j, span.b_start));
+            }
+            spans.push(span.clone());
+            i = span.a_end;
+            j = span.b_end;
+        }
+        if i < self.a_len {
+            spans.push(Span::equal(i, self.a_len, j, self.b_len));
+        }
+        spans
+    }
+
+    /// Returns the patch that converts `a` into `c`, given this patch
+    /// which converts `a` into `b` and the `next` patch which converts
+    /// `b` into `c`.
+    ///
+    /// This is much cheaper than comparing `a` and `c`, e.g., for
+    /// squashing a chain of patches; but, as for
+    /// [`compose_spans()`](fn.compose_spans.html), the result is not
+    /// always minimal.
+    ///
+    /// Returns an error if the `next` patch was not made from this
+    /// patch's `b`, i.e., if their lengths differ, or if either patch is
+    /// malformed.
+    ///
+    /// ```
+    /// use differ::{Differ, Patch};
+    ///
+    /// let a = ["a", "b", "c"];
+    /// let b = ["a", "B", "c"];
+    /// let c = ["a", "c", "d"];
+    /// let ab = Patch::new(&a, &b, &Differ::new(&a, &b).spans());
+    /// let bc = Patch::new(&b, &c, &Differ::new(&b, &c).spans());
+    /// let ac = ab.compose(&bc).unwrap();
+    /// assert_eq!(ac.apply(&a), Ok(c.to_vec()));
+    /// assert_eq!(ac.edits[0].deleted, ["b"]);
+    /// ```
+    pub fn compose(&self, next: &Patch<T>) -> Result<Self, PatchError> {
+        if next.a_len != self.b_len {
+            return Err(PatchError::WrongLength {
+                expected: self.b_len,
+                actual: next.a_len,
+            });
+        }
+        if !self.is_well_formed() || !next.is_well_formed() {
+            return Err(PatchError::Malformed);
+        }
+        let mut edits: Vec<Edit<T>> = vec![];
+        let mut open = false; // the last edit can be extended
+        for piece in compose_pieces(&self.spans(), &next.spans()) {
+            if piece.kind == Piece::Equal {
+                open = false;
+                continue;
+            }
+            if !open {
+                edits.push(Edit {
+                    span: Span::replace(
+                        piece.a, piece.a, piece.c, piece.c,
+                    ),
+                    deleted: vec![],
+                    inserted: vec![],
+                });
+                open = true;
+            }
+            let edit = edits.last_mut().unwrap();
+            let len = piece.len;
+            match piece.kind {
+                Piece::AbDelete => {
+                    let (from, i) = self.edit_at_a(piece.a);
+                    edit.deleted
+                        .extend_from_slice(&from.deleted[i..i + len]);
+                    edit.span.a_end += len;
+                }
+                Piece::BcDelete => {
+                    let (from, i) = next.edit_at_a(piece.b);
+                    edit.deleted
+                        .extend_from_slice(&from.deleted[i..i + len]);
+                    edit.span.a_end += len;
+                }
+                Piece::AbInsert => {
+                    let (from, i) = self.edit_at_b(piece.b);
+                    edit.inserted
+                        .extend_from_slice(&from.inserted[i..i + len]);
+                    edit.span.b_end += len;
+                }
+                Piece::BcInsert => {
+                    let (from, i) = next.edit_at_b(piece.c);
+                    edit.inserted
+                        .extend_from_slice(&from.inserted[i..i + len]);
+                    edit.span.b_end += len;
+                }
+                Piece::Equal => unreachable!(),
+            }
+        }
+        for edit in &mut edits {
+            if edit.deleted.is_empty() {
+                edit.span.tag = Tag::Insert;
+            } else if edit.inserted.is_empty() {
+                edit.span.tag = Tag::Delete;
+            }
+        }
+        Ok(Patch { a_len: self.a_len, b_len: next.b_len, edits })
+    }
+
+    // Returns the edit that deletes a[i] and i's offset into it.
+    fn edit_at_a(&self, i: usize) -> (&Edit<T>, usize) {
+        let index =
+            self.edits.partition_point(|edit| edit.span.a_end <= i);
+        let edit = &self.edits[index];
+        (edit, i - edit.span.a_start)
+    }
+
+    // Returns the edit that inserts b[j] and j's offset into it.
+    fn edit_at_b(&self, j: usize) -> (&Edit<T>, usize) {
+        let index =
+            self.edits.partition_point(|edit| edit.span.b_end <= j);
+        let edit = &self.edits[index];
+        (edit, j - edit.span.b_start)
+    }
+
+    // Returns true if the edits are in order, don't overlap, are
+    // consistent with their items, and fit the lengths.
+    fn is_well_formed(&self) -> bool {
+        let (mut i, mut j) = (0, 0);
+        for edit in &self.edits {
+            let span = &edit.span;
+            if span.tag == Tag::Equal
+                || span.a_start < i
+                || span.b_start < j
+                || span.a_start - i != span.b_start - j
+                || span.a_end < span.a_start
+                || span.b_end < span.b_start
+                || span.a_end - span.a_start != edit.deleted.len()
+                || span.b_end - span.b_start != edit.inserted.len()
+            {
+                return false;
+            }
+            i = span.a_end;
+            j = span.b_end;
+        }
+        i <= self.a_len
+            && j <= self.b_len
+            && self.a_len - i == self.b_len - j
+    }
+}
+
+/// Holds one hunk of a context-based patch, such as a hunk of a unified
+/// diff: the lines of context, deletions, and insertions, and where the
+/// hunk starts in sequences `a` and `b`.
+///
+/// Unlike a [`Patch`](struct.Patch.html), a `PatchHunk` can be applied
+/// to a sequence that differs from the one it was made from using
+/// [`apply_hunks()`](fn.apply_hunks.html), since its context is used to
+/// locate where it belongs.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct PatchHunk<T> {
+    pub a_start: usize,
+    pub b_start: usize,
+    pub lines: Vec<PatchLine<T>>,
+}
+
+/// One line (or item) of a [`PatchHunk`](struct.PatchHunk.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub enum PatchLine<T> {
+    /// The item is in both `a` and `b`.
+    Context(T),
+    /// The item is deleted from `a`.
+    Delete(T),
+    /// The item is inserted from `b`.
+    Insert(T),
+}
+
+impl<T> PatchHunk<T>
+where
+    T: Clone + PartialEq,
+{
+    /// Creates a new `PatchHunk` for the given
+    /// [`Hunk`](struct.Hunk.html) of `a` and `b` (e.g., as returned by
+    /// [`grouped_spans()`](struct.Differ.html#method.grouped_spans)).
+    pub fn new(a: &[T], b: &[T], hunk: &Hunk) -> Self {
+        let mut lines = vec![];
+        for span in &hunk.spans {
+            if span.tag == Tag::Equal {
+                lines.extend(
+                    a[span.a_start..span.a_end]
+                        .iter()
+                        .map(|item| PatchLine::Context(item.clone())),
+                );
+                continue;
+            }
+            lines.extend(
+                a[span.a_start..span.a_end]
+                    .iter()
+                    .map(|item| PatchLine::Delete(item.clone())),
+            );
+            lines.extend(
+                b[span.b_start..span.b_end]
+                    .iter()
+                    .map(|item| PatchLine::Insert(item.clone())),
+            );
+        }
+        PatchHunk { a_start: hunk.a_start, b_start: hunk.b_start, lines }
+    }
+
+    /// Returns the hunk that converts `b` back into `a`, i.e., that
+    /// undoes this hunk.
+    ///
+    /// The deleted lines become inserted lines and vice versa; within
+    /// each run of changed lines the deleted lines are put first, as is
+    /// conventional.
+    pub fn invert(&self) -> Self {
+        let mut lines = Vec::with_capacity(self.lines.len());
+        let mut inserted = vec![];
+        for line in &self.lines {
+            match line {
+                PatchLine::Context(item) => {
+                    lines.append(&mut inserted);
+                    lines.push(PatchLine::Context(item.clone()));
+                }
+                PatchLine::Delete(item) => {
+                    inserted.push(PatchLine::Insert(item.clone()))
+                }
+                PatchLine::Insert(item) => {
+                    lines.push(PatchLine::Delete(item.clone()))
+                }
+            }
+        }
+        lines.append(&mut inserted);
+        PatchHunk { a_start: self.b_start, b_start: self.a_start, lines }
+    }
+
+    /// Returns the number of lines the hunk covers in `a`, i.e., its
+    /// context and deleted lines.
+    pub fn a_len(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| !matches!(line, PatchLine::Insert(_)))
+            .count()
+    }
+
+    /// Returns the number of lines the hunk covers in `b`, i.e., its
+    /// context and inserted lines.
+    pub fn b_len(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| !matches!(line, PatchLine::Delete(_)))
+            .count()
+    }
+
+    /// Returns the hunk as a [`Hunk`](struct.Hunk.html), i.e., as the
+    /// spans that its lines represent.
+    ///
+    /// Each run of context lines becomes a `Tag::Equal` span, and each
+    /// run of deleted and inserted lines becomes a `Tag::Delete`,
+    /// `Tag::Insert`, or `Tag::Replace` span.
+    pub fn hunk(&self) -> Hunk {
+        let mut spans: Vec<Span> = vec![];
+        let mut i = self.a_start;
+        let mut j = self.b_start;
+        for line in &self.lines {
+            let (tag, a_len, b_len) = match line {
+                PatchLine::Context(_) => (Tag::Equal, 1, 1),
+                PatchLine::Delete(_) => (Tag::Replace, 1, 0),
+                PatchLine::Insert(_) => (Tag::Replace, 0, 1),
+            };
+            match spans.last_mut() {
+                Some(span) if span.tag == tag => {
+                    span.a_end += a_len;
+                    span.b_end += b_len;
+                }
+                _ => spans.push(Span {
+                    tag,
+                    a_start: i,
+                    a_end: i + a_len,
+                    b_start: j,
+                    b_end: j + b_len,
+                }),
+            }
+            i += a_len;
+            j += b_len;
+        }
+        for span in &mut spans {
+            if span.a_start == span.a_end {
+                span.tag = Tag::Insert;
+            } else if span.b_start == span.b_end {
+                span.tag = Tag::Delete;
+            }
+        }
+        Hunk {
+            a_start: self.a_start,
+            a_end: i,
+            b_start: self.b_start,
+            b_end: j,
+            spans,
+        }
+    }
+
+    // Returns the number of lines skipped at the start and the hunk's
+    // lines with up to fuzz context lines ignored at each end.
+    fn fuzzed_lines(&self, fuzz: usize) -> (usize, &[PatchLine<T>]) {
+        let is_context =
+            |line: &&PatchLine<T>| matches!(line, PatchLine::Context(_));
+        let leading = self.lines.iter().take_while(is_context).count();
+        let trailing = if leading == self.lines.len() {
+            0
+        } else {
+            self.lines.iter().rev().take_while(is_context).count()
+        };
+        let skip = leading.min(fuzz);
+        (skip, &self.lines[skip..self.lines.len() - trailing.min(fuzz)])
+    }
+}
+
+/// The outcome of applying one [`PatchHunk`](struct.PatchHunk.html)
+/// using [`apply_hunks()`](fn.apply_hunks.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkResult {
+    /// The hunk was applied `offset` lines away from where it said it
+    /// belonged (`0` if it was where it said), ignoring up to `fuzz` of
+    /// its outer context lines at each end (`0` if none were ignored).
+    Applied { offset: isize, fuzz: usize },
+    /// No place was found where the hunk could be applied, so it was
+    /// skipped.
+    Failed,
+}
+
+/// Returns the result of applying the hunks to sequence `a`, and the
+/// outcome for each hunk; this is the equivalent of GNU `patch`.
+///
+/// Each hunk is applied where its context and deleted lines are found:
+/// first at the line it says it starts at (adjusted by the offset at
+/// which the previous hunk was applied), and otherwise at the nearest
+/// line before or after that. If no match is found, the search is
+/// repeated ignoring one line of context at each end of the hunk, and
+/// so on, until up to `fuzz` lines have been ignored (GNU `patch`'s
+/// default is `2`). Hunks that cannot be applied are skipped, and the
+/// others are still applied. The hunks must be in order and cannot be
+/// applied overlapping each other.
+///
+/// ```
+/// use differ::{apply_hunks, Differ, HunkResult, PatchHunk};
+///
+/// let a = ["a", "b", "c", "d", "e"];
+/// let b = ["a", "b", "C", "d", "e"];
+/// let hunks = Differ::new(&a, &b)
+///     .grouped_spans(1)
+///     .iter()
+///     .map(|hunk| PatchHunk::new(&a, &b, hunk))
+///     .collect::<Vec<_>>();
+/// let stale = ["new", "a", "b", "c", "d", "e"];
+/// let (patched, results) = apply_hunks(&stale, &hunks, 0);
+/// assert_eq!(patched, ["new", "a", "b", "C", "d", "e"]);
+/// assert_eq!(results, [HunkResult::Applied { offset: 1, fuzz: 0 }]);
+/// ```
+pub fn apply_hunks<T>(
+    a: &[T],
+    hunks: &[PatchHunk<T>],
+    fuzz: usize,
+) -> (Vec<T>, Vec<HunkResult>)
+where
+    T: Clone + PartialEq,
+{
+    let mut patched = vec![];
+    let mut results = vec![];
+    let mut done = 0; // a[..done] has been copied or patched
+    let mut last_offset = 0;
+    for hunk in hunks {
+        match locate(a, done, hunk, fuzz, last_offset) {
+            Some((start, offset, fuzz)) => {
+                let (_, lines) = hunk.fuzzed_lines(fuzz);
+                patched.extend_from_slice(&a[done..start]);
+                let mut i = start;
+                for line in lines {
+                    match line {
+                        PatchLine::Context(_) => {
+                            patched.push(a[i].clone());
+                            i += 1;
+                        }
+                        PatchLine::Delete(_) => i += 1,
+                        PatchLine::Insert(item) => {
+                            patched.push(item.clone())
+                        }
+                    }
+                }
+                done = i;
+                last_offset = offset;
+                results.push(HunkResult::Applied { offset, fuzz });
+            }
+            None => results.push(HunkResult::Failed),
+        }
+    }
+    patched.extend_from_slice(&a[done..]);
+    (patched, results)
+}
+
+// Returns where the hunk's (possibly fuzzed) lines from a start in a,
+// the offset from where the hunk says they start, and the fuzz used.
+fn locate<T>(
+    a: &[T],
+    done: usize,
+    hunk: &PatchHunk<T>,
+    max_fuzz: usize,
+    last_offset: isize,
+) -> Option<(usize, isize, usize)>
+where
+    T: Clone + PartialEq,
+{
+    let mut previous_len = None;
+    for fuzz in 0..=max_fuzz {
+        let (skip, lines) = hunk.fuzzed_lines(fuzz);
+        if previous_len == Some(lines.len()) {
+            break; // there is no more context to ignore
+        }
+        previous_len = Some(lines.len());
+        let old = lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(item) | PatchLine::Delete(item) => {
+                    Some(item)
+                }
+                PatchLine::Insert(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if old.len() > a.len() - done {
+            continue;
+        }
+        let last = (a.len() - old.len()) as isize;
+        let stated = (hunk.a_start + skip) as isize;
+        let expected =
+            (stated + last_offset).max(done as isize).min(last);
+        let matches_at = |start: isize| {
+            old.iter()
+                .zip(&a[start as usize..])
+                .all(|(item, x)| *item == x)
+        };
+        let mut distance = 0;
+        while expected - distance >= done as isize
+            || expected + distance <= last
+        {
+            for start in &[expected - distance, expected + distance] {
+                if *start >= done as isize
+                    && *start <= last
+                    && matches_at(*start)
+                {
+                    return Some((
+                        *start as usize,
+                        *start - stated,
+                        fuzz,
+                    ));
+                }
+            }
+            distance += 1;
+        }
+    }
+    None
+}
+
+/// The reasons why a [`Patch`](struct.Patch.html) cannot be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The sequence has a different length from the one the patch was
+    /// made from.
+    WrongLength { expected: usize, actual: usize },
+    /// The items the patch deletes or replaces at `a_start` differ from
+    /// those the patch was made from.
+    Mismatch { a_start: usize },
+    /// The patch's edits are out of order, overlap, or are inconsistent
+    /// with its items or lengths.
+    Malformed,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PatchError::WrongLength { expected, actual } => write!(
+                f,
+                "expected a sequence of length {} but got {}",
+                expected, actual
+            ),
+            PatchError::Mismatch { a_start } => write!(
+                f,
+                "the sequence differs from the patch at {}",
+                a_start
+            ),
+            PatchError::Malformed => write!(f, "the patch is malformed"),
+        }
+    }
+}
+
+impl Error for PatchError {}
@@ -0,0 +1,120 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::myers;
+use crate::structs::Match;
+use fnv::FnvHashMap;
+use std::hash::Hash;
+
+/// Returns the matches between `a` and `b` using the patience diff
+/// algorithm.
+///
+/// Only items that occur exactly once in each (sub)sequence are used as
+/// anchors, so common items such as blank lines or closing braces never
+/// pull unrelated regions together. The longest increasing subsequence
+/// of these unique matches is kept, and the gaps between them are diffed
+/// recursively; gaps with no unique items fall back to Myers.
+///
+/// The matches are unsorted and may be adjacent: the caller is expected
+/// to sort and coalesce them.
+pub(crate) fn matches<T: Hash + Eq>(a: &[T], b: &[T]) -> Vec<Match> {
+    let mut matches = vec![];
+    let mut queue = vec![(0, a.len(), 0, b.len())];
+    while let Some((mut a_start, mut a_end, mut b_start, mut b_end)) =
+        queue.pop()
+    {
+        let prefix =
+            myers::common_prefix(a, a_start, a_end, b, b_start, b_end);
+        if prefix > 0 {
+            matches.push(Match::new(a_start, b_start, prefix));
+            a_start += prefix;
+            b_start += prefix;
+        }
+        let suffix =
+            myers::common_suffix(a, a_start, a_end, b, b_start, b_end);
+        if suffix > 0 {
+            a_end -= suffix;
+            b_end -= suffix;
+            matches.push(Match::new(a_end, b_end, suffix));
+        }
+        if a_start == a_end || b_start == b_end {
+            continue;
+        }
+        let anchors =
+            unique_anchors(a, a_start, a_end, b, b_start, b_end);
+        if anchors.is_empty() {
+            matches.extend(myers::matches_in(
+                a, a_start, a_end, b, b_start, b_end,
+            ));
+            continue;
+        }
+        let mut i = a_start;
+        let mut j = b_start;
+        for (anchor_i, anchor_j) in anchors {
+            queue.push((i, anchor_i, j, anchor_j));
+            matches.push(Match::new(anchor_i, anchor_j, 1));
+            i = anchor_i + 1;
+            j = anchor_j + 1;
+        }
+        queue.push((i, a_end, j, b_end));
+    }
+    matches
+}
+
+// Returns the (i, j) positions of the items that occur exactly once in
+// both `a[a_start..a_end]` and `b[b_start..b_end]`, reduced to the
+// longest sequence whose positions increase in both `a` and `b`.
+fn unique_anchors<T: Hash + Eq>(
+    a: &[T],
+    a_start: usize,
+    a_end: usize,
+    b: &[T],
+    b_start: usize,
+    b_end: usize,
+) -> Vec<(usize, usize)> {
+    // item -> (count in a, index in a, count in b, index in b)
+    let mut counts: FnvHashMap<&T, (usize, usize, usize, usize)> =
+        FnvHashMap::default();
+    for (i, item) in a.iter().enumerate().take(a_end).skip(a_start) {
+        let entry = counts.entry(item).or_insert((0, i, 0, 0));
+        entry.0 += 1;
+    }
+    for (j, item) in b.iter().enumerate().take(b_end).skip(b_start) {
+        if let Some(entry) = counts.get_mut(item) {
+            entry.2 += 1;
+            entry.3 = j;
+        }
+    }
+    let mut unique = counts
+        .values()
+        .filter(|(a_count, _, b_count, _)| *a_count == 1 && *b_count == 1)
+        .map(|(_, i, _, j)| (*i, *j))
+        .collect::<Vec<_>>();
+    unique.sort_unstable();
+    longest_increasing(&unique)
+}
+
+// Patience sorting: returns the longest subsequence of `pairs` (which
+// are sorted by their first element) whose second elements increase.
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut tops: Vec<usize> = vec![]; // pairs index of each pile's top
+    let mut previous: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (index, &(_, j)) in pairs.iter().enumerate() {
+        let pile = tops.partition_point(|&top| pairs[top].1 < j);
+        if pile > 0 {
+            previous[index] = Some(tops[pile - 1]);
+        }
+        if pile == tops.len() {
+            tops.push(index);
+        } else {
+            tops[pile] = index;
+        }
+    }
+    let mut result = vec![];
+    let mut index = tops.last().copied();
+    while let Some(i) = index {
+        result.push(pairs[i]);
+        index = previous[i];
+    }
+    result.reverse();
+    result
+}
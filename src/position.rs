@@ -0,0 +1,129 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::structs::{Snap, Span, Tag};
+use crate::transform::invert_spans;
+use std::ops::Range;
+
+/// Maps indexes and ranges in sequence `a` to the corresponding indexes
+/// and ranges in sequence `b`, and vice versa, e.g., to keep comments or
+/// bookmarks anchored to the right lines after an edit.
+///
+/// An index in an unchanged region maps to the same item in the other
+/// sequence. An index in a deleted or replaced region has no
+/// corresponding item, so is mapped according to the given
+/// [`Snap`](enum.Snap.html) policy. The index one past the last item
+/// maps to the index one past the other sequence's last item.
+///
+/// ```
+/// use differ::{Differ, PositionMap, Snap};
+///
+/// let a = ["a", "b", "c", "d"];
+/// let b = ["new", "a", "B", "C", "D", "d"];
+/// let map = PositionMap::new(&Differ::new(&a, &b).spans());
+/// assert_eq!(map.a_to_b(0, Snap::Gone), Some(1));
+/// assert_eq!(map.a_to_b(2, Snap::Gone), None); // "c" was replaced
+/// assert_eq!(map.a_to_b(2, Snap::Left), Some(2));
+/// assert_eq!(map.a_to_b(2, Snap::Right), Some(5));
+/// assert_eq!(map.b_to_a(5, Snap::Gone), Some(3));
+/// assert_eq!(map.a_range_to_b(0..2, Snap::Right), Some(1..5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PositionMap {
+    ab: Vec<Span>,
+    ba: Vec<Span>,
+}
+
+impl PositionMap {
+    /// Creates a new `PositionMap` from the spans that convert `a` into
+    /// `b` (e.g., as returned by
+    /// [`spans()`](struct.Differ.html#method.spans) or
+    /// [`spans_for_matches()`](fn.spans_for_matches.html)).
+    pub fn new(spans: &[Span]) -> Self {
+        PositionMap { ab: spans.to_vec(), ba: invert_spans(spans) }
+    }
+
+    /// Returns the index in `b` that corresponds to index `i` in `a`, or
+    /// `None` if `i` is in a deleted or replaced region and `snap` is
+    /// `Snap::Gone`, or if `i` is out of range.
+    pub fn a_to_b(&self, i: usize, snap: Snap) -> Option<usize> {
+        map_start(&self.ab, i, snap)
+    }
+
+    /// Returns the index in `a` that corresponds to index `j` in `b`, or
+    /// `None` if `j` is in an inserted or replaced region and `snap` is
+    /// `Snap::Gone`, or if `j` is out of range.
+    pub fn b_to_a(&self, j: usize, snap: Snap) -> Option<usize> {
+        map_start(&self.ba, j, snap)
+    }
+
+    /// Returns the range in `b` that corresponds to the `range` in `a`.
+    ///
+    /// The first item and the last item of the range are mapped using
+    /// `snap` (so a range that ends in a deleted region ends at the
+    /// start or end of the region's replacement), and `None` is returned
+    /// if either is gone. An empty range is mapped like an index.
+    pub fn a_range_to_b(
+        &self,
+        range: Range<usize>,
+        snap: Snap,
+    ) -> Option<Range<usize>> {
+        map_range(&self.ab, range, snap)
+    }
+
+    /// Returns the range in `a` that corresponds to the `range` in `b`;
+    /// see
+    /// [`a_range_to_b()`](struct.PositionMap.html#method.a_range_to_b).
+    pub fn b_range_to_a(
+        &self,
+        range: Range<usize>,
+        snap: Snap,
+    ) -> Option<Range<usize>> {
+        map_range(&self.ba, range, snap)
+    }
+}
+
+// Returns the b index of the start of item a[i].
+fn map_start(spans: &[Span], i: usize, snap: Snap) -> Option<usize> {
+    let a_len = spans.last().map_or(0, |span| span.a_end);
+    if i == a_len {
+        return Some(spans.last().map_or(0, |span| span.b_end));
+    }
+    map_item(spans, i, snap, 0)
+}
+
+// Returns the b index of the end of item a[i].
+fn map_end(spans: &[Span], i: usize, snap: Snap) -> Option<usize> {
+    map_item(spans, i, snap, 1)
+}
+
+fn map_item(
+    spans: &[Span],
+    i: usize,
+    snap: Snap,
+    offset: usize,
+) -> Option<usize> {
+    let span =
+        spans.get(spans.partition_point(|span| span.a_end <= i))?;
+    if span.tag == Tag::Equal {
+        return Some(span.b_start + i - span.a_start + offset);
+    }
+    match snap {
+        Snap::Left => Some(span.b_start),
+        Snap::Right => Some(span.b_end),
+        Snap::Gone => None,
+    }
+}
+
+fn map_range(
+    spans: &[Span],
+    range: Range<usize>,
+    snap: Snap,
+) -> Option<Range<usize>> {
+    if range.start >= range.end {
+        let start = map_start(spans, range.start, snap)?;
+        return Some(start..start);
+    }
+    let start = map_start(spans, range.start, snap)?;
+    let end = map_end(spans, range.end - 1, snap)?;
+    Some(start..end)
+}
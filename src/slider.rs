@@ -0,0 +1,308 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::structs::{Span, Tag};
+use crate::transform::push_run;
+
+// These are git's indent heuristic's tuned values (see xdiff/xdiffi.c).
+const MAX_INDENT: isize = 200;
+const MAX_BLANKS: isize = 20;
+const MAX_SLIDING: usize = 100;
+const START_OF_FILE_PENALTY: isize = 1;
+const END_OF_FILE_PENALTY: isize = 21;
+const TOTAL_BLANK_WEIGHT: isize = -30;
+const POST_BLANK_WEIGHT: isize = 6;
+const RELATIVE_INDENT_PENALTY: isize = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: isize = 10;
+const RELATIVE_OUTDENT_PENALTY: isize = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: isize = 17;
+const RELATIVE_DEDENT_PENALTY: isize = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: isize = 17;
+const INDENT_WEIGHT: isize = 60;
+
+/// Returns the spans that convert `a` into `b` with each ambiguously
+/// placed block of inserted or deleted lines slid to where a human would
+/// expect it, given the spans that convert `a` into `b` (e.g., as
+/// returned by [`spans()`](struct.Differ.html#method.spans)).
+///
+/// A `Tag::Insert` or `Tag::Delete` span that is between `Tag::Equal`
+/// spans can often be slid up or down, e.g., when a function is inserted
+/// between two others any of the blank lines or closing braces around it
+/// could be reported as inserted. This uses git's indent heuristic to
+/// choose the position whose edges best line up with blank lines and
+/// changes of indentation. A span that is slid into another change is
+/// merged with it. Other spans are left as they are.
+///
+/// ```
+/// use differ::{slide_spans, Span};
+///
+/// let a = ["fn a() {\n", "}\n", "\n", "fn c() {\n", "}\n"];
+/// let b = [
+///     "fn a() {\n", "}\n", "\n", "fn b() {\n", "}\n", "\n",
+///     "fn c() {\n", "}\n",
+/// ];
+/// // Inserts "}", "", "fn b() {"
+/// let spans = vec![
+///     Span::equal(0, 1, 0, 1),
+///     Span::insert(1, 1, 1, 4),
+///     Span::equal(1, 5, 4, 8),
+/// ];
+/// assert_eq!(
+///     slide_spans(&a, &b, &spans),
+///     vec![
+///         Span::equal(0, 3, 0, 3),
+///         Span::insert(3, 3, 3, 6), // Inserts "fn b() {", "}", ""
+///         Span::equal(3, 5, 6, 8),
+///     ]
+/// );
+/// ```
+pub fn slide_spans<T>(a: &[T], b: &[T], spans: &[Span]) -> Vec<Span>
+where
+    T: AsRef<str> + PartialEq,
+{
+    let mut spans = spans.to_vec();
+    for i in 0..spans.len() {
+        let (lines, start, end) = match spans[i].tag {
+            Tag::Delete => (a, spans[i].a_start, spans[i].a_end),
+            Tag::Insert => (b, spans[i].b_start, spans[i].b_end),
+            _ => continue,
+        };
+        let equal_len = |span: Option<&Span>| match span {
+            Some(span) if span.tag == Tag::Equal => {
+                span.a_end - span.a_start
+            }
+            _ => 0,
+        };
+        let before =
+            equal_len(i.checked_sub(1).and_then(|j| spans.get(j)));
+        let after = equal_len(spans.get(i + 1));
+        let mut up = 0;
+        while up < before && lines[start - up - 1] == lines[end - up - 1]
+        {
+            up += 1;
+        }
+        let mut down = 0;
+        while down < after && lines[start + down] == lines[end + down] {
+            down += 1;
+        }
+        if up == 0 && down == 0 {
+            continue;
+        }
+        let shift = best_end(lines, start - up, end + down, end - start);
+        if shift > end {
+            slide(&mut spans, i, shift - end, true);
+        } else if shift < end {
+            slide(&mut spans, i, end - shift, false);
+        }
+    }
+    // A change that was slid away from a change (or an end) leaves behind
+    // a gap of equal lines
+    let mut slid = vec![];
+    let (mut a_end, mut b_end) = (0, 0);
+    for span in spans {
+        if span.a_start > a_end {
+            push_run(
+                &mut slid,
+                true,
+                span.a_start - a_end,
+                span.b_start - b_end,
+            );
+        }
+        if span.a_start < span.a_end || span.b_start < span.b_end {
+            push_run(
+                &mut slid,
+                span.tag == Tag::Equal,
+                span.a_end - span.a_start,
+                span.b_end - span.b_start,
+            );
+        }
+        a_end = span.a_end;
+        b_end = span.b_end;
+    }
+    if a_end < a.len() {
+        push_run(&mut slid, true, a.len() - a_end, b.len() - b_end);
+    }
+    slid
+}
+
+// Returns the end of the best position for a block of len lines that can
+// be slid anywhere from lines[first..first + len] to lines[last - len..
+// last]. Like git, this only considers the positions furthest down and
+// prefers the furthest down of equally good ones.
+fn best_end<T: AsRef<str>>(
+    lines: &[T],
+    first: usize,
+    last: usize,
+    len: usize,
+) -> usize {
+    let lowest = (first + len)
+        .max(last.saturating_sub(len + 1))
+        .max(last.saturating_sub(MAX_SLIDING));
+    let mut best: Option<(Score, usize)> = None;
+    for end in lowest..=last {
+        let mut score = Score::default();
+        score.add(&Split::new(lines, end));
+        score.add(&Split::new(lines, end - len));
+        let better = match &best {
+            Some((best, _)) => score.cmp(best) <= 0,
+            None => true,
+        };
+        if better {
+            best = Some((score, end));
+        }
+    }
+    best.map_or(last, |(_, end)| end)
+}
+
+// Moves the change at spans[i] by n lines down (or up), shrinking and
+// growing the equal spans either side of it.
+fn slide(spans: &mut [Span], i: usize, n: usize, down: bool) {
+    let shift = |i: &mut usize| {
+        if down {
+            *i += n
+        } else {
+            *i -= n
+        }
+    };
+    shift(&mut spans[i].a_start);
+    shift(&mut spans[i].a_end);
+    shift(&mut spans[i].b_start);
+    shift(&mut spans[i].b_end);
+    if let Some(span) = i.checked_sub(1).and_then(|j| spans.get_mut(j)) {
+        if span.tag == Tag::Equal {
+            shift(&mut span.a_end);
+            shift(&mut span.b_end);
+        }
+    }
+    if let Some(span) = spans.get_mut(i + 1) {
+        if span.tag == Tag::Equal {
+            shift(&mut span.a_start);
+            shift(&mut span.b_start);
+        }
+    }
+}
+
+// The surroundings of the split just before lines[index].
+struct Split {
+    end_of_file: bool,
+    indent: isize, // of lines[index], -1 if blank
+    pre_blank: isize,
+    pre_indent: isize, // of the nearest nonblank line before
+    post_blank: isize,
+    post_indent: isize, // of the nearest nonblank line after
+}
+
+impl Split {
+    fn new<T: AsRef<str>>(lines: &[T], index: usize) -> Self {
+        let end_of_file = index >= lines.len();
+        let indent = lines.get(index).map_or(-1, |line| indent(line));
+        let (pre_blank, pre_indent) =
+            surroundings(lines[..index.min(lines.len())].iter().rev());
+        let (post_blank, post_indent) =
+            surroundings(lines.iter().skip(index + 1));
+        Split {
+            end_of_file,
+            indent,
+            pre_blank,
+            pre_indent,
+            post_blank,
+            post_indent,
+        }
+    }
+}
+
+// Returns the number of blank lines and the indent of the first nonblank
+// line (-1 if there is none).
+fn surroundings<'a, T, I>(lines: I) -> (isize, isize)
+where
+    T: AsRef<str> + 'a,
+    I: Iterator<Item = &'a T>,
+{
+    let mut blanks = 0;
+    for line in lines {
+        let indent = indent(line);
+        if indent != -1 {
+            return (blanks, indent);
+        }
+        blanks += 1;
+        if blanks == MAX_BLANKS {
+            return (blanks, 0);
+        }
+    }
+    (blanks, -1)
+}
+
+// Returns the line's indent with tabs to every eighth column, or -1 if it
+// is blank.
+fn indent<T: AsRef<str>>(line: &T) -> isize {
+    let mut indent = 0;
+    for c in line.as_ref().chars() {
+        match c {
+            ' ' => indent += 1,
+            '\t' => indent += 8 - indent % 8,
+            c if c.is_whitespace() => {}
+            _ => return indent,
+        }
+        if indent >= MAX_INDENT {
+            return MAX_INDENT;
+        }
+    }
+    -1
+}
+
+#[derive(Default)]
+struct Score {
+    effective_indent: isize,
+    penalty: isize,
+}
+
+impl Score {
+    fn add(&mut self, split: &Split) {
+        if split.pre_indent == -1 && split.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if split.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+        let post_blank =
+            if split.indent == -1 { 1 + split.post_blank } else { 0 };
+        let total_blank = split.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank;
+        self.penalty += POST_BLANK_WEIGHT * post_blank;
+        let indent = if split.indent != -1 {
+            split.indent
+        } else {
+            split.post_indent
+        };
+        let any_blanks = total_blank != 0;
+        self.effective_indent += indent;
+        if indent == -1 || split.pre_indent == -1 {
+            // No adjustment
+        } else if indent > split.pre_indent {
+            self.penalty += if any_blanks {
+                RELATIVE_INDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_INDENT_PENALTY
+            };
+        } else if indent < split.pre_indent {
+            self.penalty += if split.post_indent > indent {
+                if any_blanks {
+                    RELATIVE_OUTDENT_WITH_BLANK_PENALTY
+                } else {
+                    RELATIVE_OUTDENT_PENALTY
+                }
+            } else if any_blanks {
+                RELATIVE_DEDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_DEDENT_PENALTY
+            };
+        }
+    }
+
+    // Returns < 0 if this score is better than the other, 0 if they are
+    // equally good, and > 0 if it is worse.
+    fn cmp(&self, other: &Score) -> isize {
+        let indents =
+            (self.effective_indent - other.effective_indent).signum();
+        INDENT_WEIGHT * indents + self.penalty - other.penalty
+    }
+}
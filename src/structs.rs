@@ -20,6 +20,34 @@ impl Match {
     }
 }
 
+/// The algorithm a [`Differ`](struct.Differ.html) uses to find the
+/// matches between its two sequences.
+///
+/// Every algorithm produces the same kind of
+/// [`Match`](struct.Match.html)es and [`Span`](struct.Span.html)s, so
+/// they are interchangeable; they differ in speed and in which of several
+/// equally valid answers they prefer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub enum Algorithm {
+    /// The Python `difflib.SequenceMatcher` longest-match recursion (the
+    /// default). Produces diffs that "look right" to people, but not
+    /// necessarily a minimal edit script.
+    #[default]
+    SequenceMatcher,
+    /// Myers' O(ND) algorithm (linear space variant). Always produces a
+    /// minimal edit script and is fast when the sequences are similar.
+    Myers,
+    /// Patience diff. Anchors only on items that are unique in both
+    /// sequences, so common items like blank lines or closing braces
+    /// don't produce spurious matches; good for source code.
+    Patience,
+    /// Histogram diff (as used by git's `--histogram`). Like patience,
+    /// but anchors on the least frequent items rather than only on
+    /// unique ones; usually the most readable for source code.
+    Histogram,
+}
+
 /// Used in a [`Span`](struct.Span.html) to indicate what kind of span it
 /// is.
 #[derive(Debug, Clone, PartialEq)]
@@ -100,3 +128,163 @@ impl Span {
         Span { tag: Tag::Replace, a_start, a_end, b_start, b_end }
     }
 }
+
+/// Holds a group of [`Span`](struct.Span.html)s that are close enough
+/// together to be shown as one block of changes with some surrounding
+/// context (e.g., one `@@` block in a unified diff), and the half-open
+/// ranges they cover in sequences `a` and `b`.
+///
+/// The first and last spans are usually `Tag::Equal` spans holding the
+/// context.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct Hunk {
+    pub a_start: usize,
+    pub a_end: usize,
+    pub b_start: usize,
+    pub b_end: usize,
+    pub spans: Vec<Span>,
+}
+
+/// The unit in which the changes within lines are found by
+/// [`intraline_spans()`](fn.intraline_spans.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub enum Granularity {
+    /// Compare the lines character by character.
+    Char,
+    /// Compare the lines word by word, where a word is a run of
+    /// alphanumeric characters (or underscores), a run of whitespace, or
+    /// a single punctuation character.
+    Word,
+}
+
+/// Holds a line from sequence `a` and the line from sequence `b` that
+/// replaces it, with the spans that convert one into the other.
+///
+/// The spans' indexes are byte offsets into the two lines, so can be
+/// used to slice them, e.g., `&a_line[span.a_start..span.a_end]`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct LinePair {
+    pub a_line: usize,
+    pub b_line: usize,
+    pub spans: Vec<Span>,
+}
+
+/// How [`word_diff()`](fn.word_diff.html) marks up the changed words;
+/// these correspond to `git diff --word-diff`'s modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub enum WordDiffStyle {
+    /// Deleted text is shown as `[-text-]` and inserted text as
+    /// `{+text+}`.
+    Plain,
+    /// Each run of common, deleted, or inserted text is put on its own
+    /// line prefixed with `' '`, `'-'`, or `'+'`, and each newline in the
+    /// text is shown as a line containing just `~`.
+    Porcelain,
+    /// Deleted text is shown in red and inserted text in green using
+    /// ANSI escape sequences.
+    Colour,
+}
+
+/// What kind of region a [`MergeRegion`](struct.MergeRegion.html) is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub enum MergeTag {
+    /// Neither ours nor theirs changed the base.
+    Unchanged,
+    /// Only ours changed the base, so ours is used.
+    Ours,
+    /// Only theirs changed the base, so theirs is used.
+    Theirs,
+    /// Ours and theirs made the same change to the base, so either is
+    /// used.
+    Both,
+    /// Ours and theirs made different changes to the base, so the
+    /// conflict must be resolved by hand.
+    Conflict,
+}
+
+impl fmt::Display for MergeTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tag = match *self {
+            MergeTag::Unchanged => "unchanged",
+            MergeTag::Ours => "ours",
+            MergeTag::Theirs => "theirs",
+            MergeTag::Both => "both",
+            MergeTag::Conflict => "conflict",
+        };
+        write!(f, "{}", tag)
+    }
+}
+
+/// Holds the data describing one region of a three-way merge: what kind
+/// of region it is (the [`MergeTag`](enum.MergeTag.html)) and the start
+/// and end indexes in the base, ours, and theirs sequences.
+///
+/// The indexes are half-open ranges; every region has all three ranges
+/// (e.g., for a `MergeTag::Ours` region the theirs range holds the same
+/// items as the base range).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct MergeRegion {
+    pub tag: MergeTag,
+    pub base_start: usize,
+    pub base_end: usize,
+    pub ours_start: usize,
+    pub ours_end: usize,
+    pub theirs_start: usize,
+    pub theirs_end: usize,
+}
+
+/// What a [`PositionMap`](struct.PositionMap.html) does with a position
+/// inside a deleted or replaced region, i.e., one that has no
+/// corresponding position in the other sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub enum Snap {
+    /// Map the position to the start of the region's replacement.
+    Left,
+    /// Map the position to the end of the region's replacement.
+    Right,
+    /// Report the position as gone (i.e., return `None`).
+    Gone,
+}
+
+/// Holds a block of items that was moved: deleted from `a` at
+/// `a_start..a_end` and inserted into `b` at `b_start..b_end`, as found
+/// by [`detect_moves()`](fn.detect_moves.html).
+///
+/// The `ratio` is the similarity of the two blocks in the range
+/// `0.0..=1.0`, where `1.0` means the block was moved unchanged.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct Move {
+    pub a_start: usize,
+    pub a_end: usize,
+    pub b_start: usize,
+    pub b_end: usize,
+    pub ratio: f64,
+}
+
+/// Holds the cost of each kind of edit used by
+/// [`EditDistance`](struct.EditDistance.html); by default each costs `1`.
+///
+/// A `transpose` swaps two adjacent items and is only used when
+/// transpositions are turned on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature="use_serde", derive(Serialize, Deserialize))]
+pub struct EditCosts {
+    pub insert: usize,
+    pub delete: usize,
+    pub substitute: usize,
+    pub transpose: usize,
+}
+
+impl Default for EditCosts {
+    fn default() -> Self {
+        EditCosts { insert: 1, delete: 1, substitute: 1, transpose: 1 }
+    }
+}
@@ -2,7 +2,19 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::{Differ, Match, Span, spans_for_matches, Tag};
+    use crate::{
+        apply_hunks, cleanup_efficiency, cleanup_semantic,
+        cleanup_semantic_lossless, close_matches, compose_spans,
+        context_diff, context_diff_for_hunks, damerau_levenshtein,
+        detect_moves, hunks_for_spans, intraline_spans, invert_spans,
+        levenshtein, merge3, merge3_text, ndiff, parse_unified_diff,
+        slide_spans, spans_for_matches, unified_diff,
+        unified_diff_for_hunks, word_diff, word_spans, Algorithm, Differ,
+        Edit, EditCosts, EditDistance, Granularity, HtmlDiff, Hunk,
+        HunkResult, LinePair, Match, MergeRegion, MergeTag, Move,
+        ParseError, Patch, PatchError, PatchHunk, PatchLine, PositionMap,
+        Snap, Span, Tag, WordDiffStyle,
+    };
 
     #[test]
     fn t01() {
@@ -138,11 +150,13 @@ mod tests {
             Span::insert(1, 1, 1, 2), // -> baz
             Span::delete(2, 3, 3, 3), // baz ->
         ];
-        let mut i = 0;
-        for span in differ.spans().iter().filter(|s| s.tag != Tag::Equal)
+        for (i, span) in differ
+            .spans()
+            .iter()
+            .filter(|s| s.tag != Tag::Equal)
+            .enumerate()
         {
             assert_eq!(span, &expected[i]);
-            i += 1;
         }
     }
 
@@ -230,6 +244,9 @@ mod tests {
             Item::new(6, 1, "E"),
             Item::new(4, 2, "G"),
         ];
+        // Only the text is compared
+        assert_eq!(a[0], b[0]);
+        assert_ne!((a[0].x, a[0].y), (b[0].x, b[0].y));
         let differ = Differ::new(&a, &b);
         let expected = vec![
             Span::equal(0, 1, 0, 1),  // A
@@ -324,4 +341,1544 @@ mod tests {
         let differ = Differ::new(&a_words, &b_words);
         assert_eq!(differ.spans().len(), 0);
     }
+
+    #[test]
+    fn t17() {
+        let a = "abcabba".chars().collect::<Vec<_>>();
+        let b = "cbabac".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&a, &b).algorithm(Algorithm::Myers);
+        let expected = vec![
+            Span::delete(0, 2, 0, 0), // a b ->
+            Span::equal(2, 3, 0, 1),  // c
+            Span::delete(3, 4, 1, 1), // a ->
+            Span::equal(4, 5, 1, 2),  // b
+            Span::insert(5, 5, 2, 3), // -> a
+            Span::equal(5, 7, 3, 5),  // b a
+            Span::insert(7, 7, 5, 6), // -> c
+        ];
+        let spans = differ.spans();
+        assert_eq!(spans.len(), expected.len());
+        for (i, span) in spans.iter().enumerate() {
+            assert_eq!(span, &expected[i]);
+        }
+    }
+
+    #[test]
+    fn t18() {
+        let a = "the quick brown fox jumped over the lazy dogs";
+        let b = "the quick red fox jumped over the very busy dogs";
+        let a_words = a.split_whitespace().collect::<Vec<_>>();
+        let b_words = b.split_whitespace().collect::<Vec<_>>();
+        let differ =
+            Differ::new(&a_words, &b_words).algorithm(Algorithm::Myers);
+        let expected = vec![
+            Match::new(0, 0, 2),
+            Match::new(3, 3, 4),
+            Match::new(8, 9, 1),
+            Match::new(9, 10, 0),
+        ];
+        assert_eq!(differ.matches(), expected);
+        let sequence_matcher = Differ::new(&a_words, &b_words);
+        assert_eq!(differ.spans(), sequence_matcher.spans());
+    }
+
+    #[test]
+    fn t19() {
+        // Myers always finds a longest common subsequence, which the
+        // SequenceMatcher recursion need not.
+        let a = "cac".chars().collect::<Vec<_>>();
+        let b = "abc".chars().collect::<Vec<_>>();
+        let same = |matches: Vec<Match>| -> usize {
+            matches.iter().map(|m| m.length).sum()
+        };
+        let differ = Differ::new(&a, &b);
+        assert_eq!(same(differ.matches()), 1); // c
+        let differ = differ.algorithm(Algorithm::Myers);
+        assert_eq!(same(differ.matches()), 2); // a c
+        let differ = Differ::new(&a[..0], &b).algorithm(Algorithm::Myers);
+        assert_eq!(differ.spans(), vec![Span::insert(0, 0, 0, 3)]);
+    }
+
+    #[test]
+    fn t20() {
+        let a = "void f() {\n    a();\n}\nvoid g() {\n    b();\n}\n\
+                 void h() {\n    c();\n}";
+        let b = "void g() {\n    b();\n}\nvoid f() {\n    a();\n}\n\
+                 void h() {\n    c();\n}";
+        let a_lines = a.lines().collect::<Vec<_>>();
+        let b_lines = b.lines().collect::<Vec<_>>();
+        let differ = Differ::new(&a_lines, &b_lines)
+            .algorithm(Algorithm::Patience);
+        let expected = vec![
+            Span::delete(0, 3, 0, 0), // void f() { a(); } ->
+            Span::equal(3, 5, 0, 2),  // void g() { b();
+            Span::insert(5, 5, 2, 5), // -> } void f() { a();
+            Span::equal(5, 9, 5, 9),  // } void h() { c(); }
+        ];
+        assert_eq!(differ.spans(), expected);
+    }
+
+    #[test]
+    fn t21() {
+        // The only items unique to both sequences are 1, 2 and 3, and of
+        // these only 1 and 3 appear in the same order, so the repeated 0s
+        // and 9s are aligned within the gaps either side of 1 and 3.
+        let a = &[0, 1, 9, 2, 0, 9, 3, 0];
+        let b = &[9, 1, 0, 0, 3, 9, 2, 0];
+        let differ =
+            Differ::new(&a[..], &b[..]).algorithm(Algorithm::Patience);
+        let expected = vec![
+            Match::new(1, 1, 1),
+            Match::new(4, 3, 1),
+            Match::new(6, 4, 1),
+            Match::new(7, 7, 1),
+            Match::new(8, 8, 0),
+        ];
+        assert_eq!(differ.matches(), expected);
+        let differ =
+            Differ::new(&a[..], &a[..]).algorithm(Algorithm::Patience);
+        assert_eq!(differ.spans(), vec![Span::equal(0, 8, 0, 8)]);
+    }
+
+    #[test]
+    fn t22() {
+        // Histogram anchors on the rarest items (here 7, which occurs
+        // once) in preference to a longer run of more common ones.
+        let a = &[1, 2, 1, 2, 1, 2, 7, 4];
+        let b = &[4, 7, 1, 2, 1, 2, 1, 2];
+        let differ =
+            Differ::new(&a[..], &b[..]).algorithm(Algorithm::Histogram);
+        let expected = vec![
+            Span::replace(0, 6, 0, 1), // 1 2 1 2 1 2 -> 4
+            Span::equal(6, 7, 1, 2),   // 7
+            Span::replace(7, 8, 2, 8), // 4 -> 1 2 1 2 1 2
+        ];
+        assert_eq!(differ.spans(), expected);
+        let differ = differ.algorithm(Algorithm::SequenceMatcher);
+        assert_eq!(differ.matches()[0], Match::new(0, 2, 6));
+    }
+
+    #[test]
+    fn t23() {
+        let a = "the quick brown fox jumped over the lazy dogs";
+        let b = "the quick red fox jumped over the very busy dogs";
+        let a_words = a.split_whitespace().collect::<Vec<_>>();
+        let b_words = b.split_whitespace().collect::<Vec<_>>();
+        let differ = Differ::new(&a_words, &b_words)
+            .algorithm(Algorithm::Histogram);
+        let sequence_matcher = Differ::new(&a_words, &b_words);
+        assert_eq!(differ.spans(), sequence_matcher.spans());
+        // The only common item is too common to anchor on, so Myers is
+        // used.
+        let mut a = vec![0; 100];
+        a.insert(0, 1);
+        let mut b = vec![0; 90];
+        b.push(2);
+        let differ = Differ::new(&a, &b).algorithm(Algorithm::Histogram);
+        let expected = vec![
+            Span::delete(0, 2, 0, 0),
+            Span::equal(2, 92, 0, 90),
+            Span::replace(92, 101, 90, 91),
+        ];
+        assert_eq!(differ.spans(), expected);
+    }
+
+    #[test]
+    fn t24() {
+        let a = vec!["x"; 300];
+        let mut b = a.clone();
+        b.insert(150, "y");
+        let differ = Differ::new(&a, &b);
+        assert_eq!(differ.popular(), vec![&"x"]);
+        let expected = vec![
+            Span::equal(0, 150, 0, 150),
+            Span::replace(150, 300, 150, 301),
+        ];
+        assert_eq!(differ.spans(), expected);
+        let expected = vec![
+            Span::equal(0, 150, 0, 150),
+            Span::insert(150, 150, 150, 151),
+            Span::equal(150, 300, 151, 301),
+        ];
+        let differ = differ.autojunk(false);
+        assert!(differ.popular().is_empty());
+        assert_eq!(differ.spans(), expected);
+        let differ = Differ::new(&a, &b).autojunk_threshold(400, 1.0);
+        assert!(differ.popular().is_empty());
+        assert_eq!(differ.spans(), expected);
+    }
+
+    #[test]
+    fn t25() {
+        let a = "abc".chars().collect::<Vec<_>>();
+        let b = "bcbcbcbcaxyz".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&a, &b);
+        assert!(differ.popular().is_empty()); // b is too short
+        let differ = differ.autojunk_threshold(10, 20.0);
+        let mut popular = differ.popular();
+        popular.sort();
+        assert_eq!(popular, vec![&'b', &'c']); // 4 > (20% of 12) + 1
+        let differ = differ.autojunk_threshold(10, 25.0);
+        assert!(differ.popular().is_empty()); // 4 <= (25% of 12) + 1
+    }
+
+    #[test]
+    fn t26() {
+        // From Python's difflib documentation.
+        let a = " abcd".chars().collect::<Vec<_>>();
+        let b = "abcd abcd".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&a, &b);
+        assert_eq!(differ.longest_match(0, 5, 0, 9), Match::new(0, 4, 5));
+        let differ = differ.junk(|c| *c == ' ');
+        assert_eq!(differ.longest_match(0, 5, 0, 9), Match::new(1, 0, 4));
+    }
+
+    #[test]
+    fn t27() {
+        // Compare with t03: junk spaces can extend but not anchor.
+        let a = b"private Thread currentThread;";
+        let b = b"private volatile Thread currentThread;";
+        let differ = Differ::new(&a[..], &b[..])
+            .junk(|c| *c == b' ' || *c == b'\t');
+        let expected = vec![
+            Span::equal(0, 8, 0, 8),    // private
+            Span::insert(8, 8, 8, 17),  // -> volatile
+            Span::equal(8, 29, 17, 38), // Thread currentThread;
+        ];
+        assert_eq!(differ.spans(), expected);
+    }
+
+    #[test]
+    fn t28() {
+        // Expected values are from Python's difflib.
+        let a = b"private Thread currentThread;";
+        let b = b"private volatile Thread currentThread;";
+        let differ = Differ::new(&a[..], &b[..]);
+        let expected = 0.8656716417910447;
+        assert!((differ.ratio() - expected).abs() < 1e-12);
+        assert!((differ.quick_ratio() - expected).abs() < 1e-12);
+        assert!((differ.real_quick_ratio() - expected).abs() < 1e-12);
+        let a = "abc".chars().collect::<Vec<_>>();
+        let b = "cba".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&a, &b);
+        assert!((differ.ratio() - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(differ.quick_ratio(), 1.0);
+        assert_eq!(differ.real_quick_ratio(), 1.0);
+    }
+
+    #[test]
+    fn t29() {
+        let a: Vec<char> = vec![];
+        let b = "xyz".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&a, &a);
+        assert_eq!(differ.ratio(), 1.0);
+        assert_eq!(differ.quick_ratio(), 1.0);
+        assert_eq!(differ.real_quick_ratio(), 1.0);
+        let differ = Differ::new(&a, &b);
+        assert_eq!(differ.ratio(), 0.0);
+        assert_eq!(differ.quick_ratio(), 0.0);
+        assert_eq!(differ.real_quick_ratio(), 0.0);
+        let c = "xxyy".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&b, &c);
+        assert!((differ.quick_ratio() - 4.0 / 7.0).abs() < 1e-12);
+        assert!((differ.real_quick_ratio() - 6.0 / 7.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn t30() {
+        // Expected values are from Python's difflib.
+        let fruit = ["ape", "apple", "peach", "puppy"];
+        let matches = close_matches("appel", &fruit, 3, 0.6);
+        assert_eq!(matches, ["apple", "ape"]);
+        let words = ["while", "wheal", "whale", "help", "hello", "wheel"];
+        assert_eq!(
+            close_matches("wheel", &words, 3, 0.5),
+            ["wheel", "wheal", "help"]
+        );
+        assert!(close_matches("xyz", &fruit, 3, 0.6).is_empty());
+        assert_eq!(close_matches("peach", &fruit, 1, 1.0), ["peach"]);
+    }
+
+    #[test]
+    fn t31() {
+        let a = "abcd".chars().collect::<Vec<_>>();
+        let b = "bcde".chars().collect::<Vec<_>>();
+        let c = "bcd".chars().collect::<Vec<_>>();
+        let mut differ = Differ::new(&a, &b);
+        assert_eq!(differ.ratio(), 0.75);
+        differ.set_a(&c);
+        assert_eq!(differ.matches()[0], Match::new(0, 0, 3));
+        assert!((differ.ratio() - 6.0 / 7.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn t32() {
+        // Expected values are from Python's difflib.
+        let a = "abcdefghij".chars().collect::<Vec<_>>();
+        let b = "abXdefghYj".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&a, &b);
+        let expected = vec![
+            Hunk {
+                a_start: 0,
+                a_end: 5,
+                b_start: 0,
+                b_end: 5,
+                spans: vec![
+                    Span::equal(0, 2, 0, 2),
+                    Span::replace(2, 3, 2, 3),
+                    Span::equal(3, 5, 3, 5),
+                ],
+            },
+            Hunk {
+                a_start: 6,
+                a_end: 10,
+                b_start: 6,
+                b_end: 10,
+                spans: vec![
+                    Span::equal(6, 8, 6, 8),
+                    Span::replace(8, 9, 8, 9),
+                    Span::equal(9, 10, 9, 10),
+                ],
+            },
+        ];
+        assert_eq!(differ.grouped_spans(2), expected);
+        let hunks = differ.grouped_spans(3); // 3 + 3 > 5 equal between
+        assert_eq!(hunks.len(), 1);
+        assert_eq!((hunks[0].a_start, hunks[0].a_end), (0, 10));
+        assert_eq!(hunks[0].spans.len(), 5);
+    }
+
+    #[test]
+    fn t33() {
+        let a = "abcdefghij".chars().collect::<Vec<_>>();
+        let b = "abXdefghYj".chars().collect::<Vec<_>>();
+        let differ = Differ::new(&a, &b);
+        let hunks = differ.grouped_spans(0);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].spans, vec![Span::replace(2, 3, 2, 3)]);
+        assert_eq!((hunks[1].a_start, hunks[1].a_end), (8, 9));
+        assert!(Differ::new(&a, &a).grouped_spans(3).is_empty());
+        assert!(hunks_for_spans(&[], 3).is_empty());
+        let hunks = hunks_for_spans(&[Span::insert(0, 0, 0, 4)], 3);
+        assert_eq!(hunks[0].spans, vec![Span::insert(0, 0, 0, 4)]);
+    }
+
+    #[test]
+    fn t34() {
+        // Expected values are from GNU diff -u.
+        let diff = unified_diff("", "x\ny\n", "a", "b", "", "", 3);
+        assert_eq!(diff, "--- a\n+++ b\n@@ -0,0 +1,2 @@\n+x\n+y\n");
+        let diff = unified_diff("x\ny\n", "", "a", "b", "", "", 3);
+        assert_eq!(diff, "--- a\n+++ b\n@@ -1,2 +0,0 @@\n-x\n-y\n");
+        let diff = unified_diff("x", "x\n", "a", "b", "", "", 3);
+        assert_eq!(
+            diff,
+            "--- a\n+++ b\n@@ -1 +1 @@\n-x\n\
+             \\ No newline at end of file\n+x\n"
+        );
+        assert_eq!(unified_diff("x\ny", "x\ny", "a", "b", "", "", 3), "");
+        // GNU diff puts the inserted function after the blank line
+        let a = "fn a() {\n    1\n}\n\nfn c() {\n    3\n}\n";
+        let b = "fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n\n\
+                 fn c() {\n    3\n}\n";
+        let expected =
+            "--- a\n+++ b\n@@ -2,6 +2,10 @@\n     1\n }\n \n\
+             +fn b() {\n+    2\n+}\n+\n fn c() {\n     3\n }\n";
+        assert_eq!(unified_diff(a, b, "a", "b", "", "", 3), expected);
+        // GNU diff slides a deletion down to join the change after it
+        let a = "b\nc\na\na\nb\nb\na\nb\nc\nb\nb\n";
+        let b = "c\na\na\nc\na\nc\nb\na\n";
+        let expected = "\
+--- a
++++ b
+@@ -1,11 +1,8 @@
+-b
+ c
+ a
+ a
+-b
+-b
++c
+ a
+-b
+ c
+ b
+-b
++a
+";
+        assert_eq!(unified_diff(a, b, "a", "b", "", "", 3), expected);
+    }
+
+    #[test]
+    fn t35() {
+        let a = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let b = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+        let diff = unified_diff(
+            a,
+            b,
+            "old/n.txt",
+            "new/n.txt",
+            "2019-03-08 10:31:02.000000000 +0000",
+            "2019-03-09 08:15:44.000000000 +0000",
+            1,
+        );
+        let expected = "\
+--- old/n.txt\t2019-03-08 10:31:02.000000000 +0000
++++ new/n.txt\t2019-03-09 08:15:44.000000000 +0000
+@@ -4,3 +4,3 @@
+ 4
+-5
++five
+ 6
+@@ -10 +10,2 @@
+ 10
++11
+";
+        assert_eq!(diff, expected);
+        let a_lines = a.split_inclusive('\n').collect::<Vec<_>>();
+        let b_lines = b.split_inclusive('\n').collect::<Vec<_>>();
+        let hunks = Differ::new(&a_lines, &b_lines)
+            .algorithm(Algorithm::Myers)
+            .grouped_spans(1);
+        let diff = unified_diff_for_hunks(
+            &a_lines,
+            &b_lines,
+            &hunks,
+            "old/n.txt",
+            "new/n.txt",
+            "2019-03-08 10:31:02.000000000 +0000",
+            "2019-03-09 08:15:44.000000000 +0000",
+        );
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    fn t36() {
+        // Expected values are from GNU diff -c.
+        let a = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let b = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11";
+        let expected = "\
+*** old
+--- new
+***************
+*** 4,6 ****
+  4
+! 5
+  6
+--- 4,6 ----
+  4
+! five
+  6
+***************
+*** 10 ****
+--- 10,11 ----
+  10
++ 11
+\\ No newline at end of file
+";
+        assert_eq!(context_diff(a, b, "old", "new", "", "", 1), expected);
+        assert_eq!(context_diff(a, a, "old", "new", "", "", 1), "");
+        let a = "fn a() {\n    1\n}\n\nfn c() {\n    3\n}\n";
+        let b = "fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n\n\
+                 fn c() {\n    3\n}\n";
+        let expected = "*** a\n--- b\n***************\n*** 2,7 ****\n\
+                        --- 2,11 ----\n      1\n  }\n  \n+ fn b() {\n\
+                        +     2\n+ }\n+ \n  fn c() {\n      3\n  }\n";
+        assert_eq!(context_diff(a, b, "a", "b", "", "", 3), expected);
+        // Expected value is from difflib's context_diff() (and is the
+        // same as GNU diff -c's).
+        let a = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\n\
+                 ten\n";
+        let b = "one\nTWO\nthree\nfour\nfive\nsix\nseven\neight\nnine\n\
+                 ten\neleven\n";
+        let expected = "\
+*** old.txt\t2019-03-08 10:31:02
+--- new.txt\t2019-03-09 08:15:44
+***************
+*** 1,4 ****
+  one
+! two
+  three
+  four
+--- 1,4 ----
+  one
+! TWO
+  three
+  four
+***************
+*** 9,10 ****
+--- 9,11 ----
+  nine
+  ten
++ eleven
+";
+        let diff = context_diff(
+            a,
+            b,
+            "old.txt",
+            "new.txt",
+            "2019-03-08 10:31:02",
+            "2019-03-09 08:15:44",
+            2,
+        );
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    fn t37() {
+        let a = "x\ny\n".split_inclusive('\n').collect::<Vec<_>>();
+        let b: Vec<&str> = vec![];
+        let hunks = Differ::new(&a, &b).grouped_spans(3);
+        let diff = context_diff_for_hunks(
+            &a,
+            &b,
+            &hunks,
+            "a",
+            "b",
+            "2019-03-08 10:31:02",
+            "2019-03-09 08:15:44",
+        );
+        let expected = "\
+*** a\t2019-03-08 10:31:02
+--- b\t2019-03-09 08:15:44
+***************
+*** 1,2 ****
+- x
+- y
+--- 0 ----
+";
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    fn t38() {
+        // Expected values are from Python's difflib.
+        let diff = ndiff(&["abcDefghiJkl\n"], &["abcdefGhijkl\n"]);
+        let expected = vec![
+            "- abcDefghiJkl\n",
+            "?    ^  ^  ^\n",
+            "+ abcdefGhijkl\n",
+            "?    ^  ^  ^\n",
+        ];
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    fn t39() {
+        // Expected values are from Python's difflib.
+        let a = ["\tif x == 1:\n", "\t\treturn a\n", "\tfoo()\n"];
+        let b = ["\tif x == 2:\n", "\t\treturn a\n", "bar\n"];
+        let expected = vec![
+            "- \tif x == 1:\n",
+            "? \t        ^\n",
+            "+ \tif x == 2:\n",
+            "? \t        ^\n",
+            "  \t\treturn a\n",
+            "- \tfoo()\n",
+            "+ bar\n",
+        ];
+        assert_eq!(ndiff(&a, &b), expected);
+        assert!(ndiff(&[], &[]).is_empty());
+        assert_eq!(ndiff(&["x"], &[]), vec!["- x"]);
+    }
+
+    #[test]
+    fn t40() {
+        let a = ["a & b\n", "\tx\n", "same\n"];
+        let b = ["a & c\n", "\tx\n", "same\n", "new\n"];
+        let html = HtmlDiff::new().tab_size(4);
+        let table = html.table(&a, &b, "<old>", "new");
+        let expected = r#"<table class="diff">
+<thead><tr><th colspan="2">&lt;old&gt;</th><th colspan="2">new</th></tr></thead>
+<tbody>
+<tr><td class="diff_header">1</td><td nowrap="nowrap">a&nbsp;&amp;&nbsp;<span class="diff_chg">b</span></td><td class="diff_header">1</td><td nowrap="nowrap">a&nbsp;&amp;&nbsp;<span class="diff_chg">c</span></td></tr>
+<tr><td class="diff_header">2</td><td nowrap="nowrap">&nbsp;&nbsp;&nbsp;&nbsp;x</td><td class="diff_header">2</td><td nowrap="nowrap">&nbsp;&nbsp;&nbsp;&nbsp;x</td></tr>
+<tr><td class="diff_header">3</td><td nowrap="nowrap">same</td><td class="diff_header">3</td><td nowrap="nowrap">same</td></tr>
+<tr><td class="diff_header"></td><td nowrap="nowrap"></td><td class="diff_header">4</td><td nowrap="nowrap"><span class="diff_add">new</span></td></tr>
+</tbody>
+</table>
+"#;
+        assert_eq!(table, expected);
+        let file = html.file(&a, &b, "<old>", "new");
+        assert!(file.starts_with("<!DOCTYPE html>"));
+        assert!(file.contains(expected));
+    }
+
+    #[test]
+    fn t41() {
+        let a = (1..=10).map(|i| format!("{}\n", i)).collect::<Vec<_>>();
+        let mut b = a.clone();
+        b[1] = "two\n".to_string();
+        b[8] = "nine\n".to_string();
+        let a = a.iter().map(|line| line.as_str()).collect::<Vec<_>>();
+        let b = b.iter().map(|line| line.as_str()).collect::<Vec<_>>();
+        let html = HtmlDiff::new().context(1);
+        let table = html.table(&a, &b, "old", "new");
+        assert_eq!(table.matches("<tbody>").count(), 2);
+        assert_eq!(table.matches("<tr><td").count(), 6);
+        assert!(!table.contains(">5</td>"));
+        assert!(table.contains(r#"<span class="diff_chg">nine</span>"#));
+        let table = html.table(&a, &a, "old", "new");
+        assert!(table.contains("No Differences Found"));
+        let table = HtmlDiff::new().table(&a, &a, "old", "new");
+        assert_eq!(table.matches("<tr><td").count(), 10);
+    }
+
+    #[test]
+    fn t42() {
+        let a = ["unchanged", "café au lait", "gone"];
+        let b = ["unchanged", "xyz", "cafe au lait!"];
+        let spans = Differ::new(&a, &b).spans();
+        assert_eq!(spans[1], Span::replace(1, 3, 1, 3));
+        let pairs =
+            intraline_spans(&a, &b, &spans, Granularity::Char, 0.6);
+        let expected = vec![LinePair {
+            a_line: 1,
+            b_line: 2,
+            spans: vec![
+                Span::equal(0, 3, 0, 3),
+                Span::replace(3, 5, 3, 4),
+                Span::equal(5, 13, 4, 12),
+                Span::insert(13, 13, 12, 13),
+            ],
+        }];
+        assert_eq!(pairs, expected);
+        assert_eq!(&a[1][3..5], "é");
+        let pairs =
+            intraline_spans(&a, &b, &spans, Granularity::Char, 0.99);
+        assert!(pairs.is_empty());
+    }
+
+    #[test]
+    fn t43() {
+        let a = ["fn add(x: i32) -> i32 {"];
+        let b = ["fn add(x: i64, y: i64) -> i64 {"];
+        let spans = Differ::new(&a, &b).spans();
+        let pairs =
+            intraline_spans(&a, &b, &spans, Granularity::Word, 0.5);
+        let changed = pairs[0]
+            .spans
+            .iter()
+            .filter(|span| span.tag != Tag::Equal)
+            .map(|span| {
+                let a_text = &a[0][span.a_start..span.a_end];
+                let b_text = &b[0][span.b_start..span.b_end];
+                (a_text, b_text)
+            })
+            .collect::<Vec<_>>();
+        let expected = vec![("i32", "i64, y: i64"), ("i32", "i64")];
+        assert_eq!(changed, expected);
+    }
+
+    #[test]
+    fn t44() {
+        let a = "one  two\tthree\nfour five\n";
+        let b = "one  2\tthree\nfour\nfive six\n";
+        let spans = word_spans(a, b);
+        let mut a_text = String::new();
+        let mut b_text = String::new();
+        for span in &spans {
+            a_text.push_str(&a[span.a_start..span.a_end]);
+            b_text.push_str(&b[span.b_start..span.b_end]);
+        }
+        assert_eq!(a_text, a);
+        assert_eq!(b_text, b);
+        assert_eq!(
+            word_diff(a, b, WordDiffStyle::Plain),
+            "one  [-two-]{+2+}\tthree\nfour\n{+five+} [-five-]{+six+}\n"
+        );
+        assert_eq!(
+            word_diff(a, b, WordDiffStyle::Porcelain),
+            " one  \n-two\n+2\n \tthree\n~\n four\n~\n+five\n  \n\
+             -five\n+six\n~\n"
+        );
+        assert_eq!(
+            word_diff(a, b, WordDiffStyle::Colour),
+            "one  \x1B[31mtwo\x1B[m\x1B[32m2\x1B[m\tthree\nfour\n\
+             \x1B[32mfive\x1B[m \x1B[31mfive\x1B[m\x1B[32msix\x1B[m\n"
+        );
+    }
+
+    #[test]
+    fn t45() {
+        let base = ["1", "2", "3", "4", "5", "6", "7"];
+        let ours = ["1", "two", "3", "4", "5", "6", "7", "8"];
+        let theirs = ["1", "two", "3", "four", "5", "six", "7"];
+        let mut ours_six = ours;
+        ours_six[5] = "SIX";
+        let regions = merge3(&base, &ours_six, &theirs);
+        let expected = vec![
+            MergeRegion {
+                tag: MergeTag::Unchanged,
+                base_start: 0,
+                base_end: 1,
+                ours_start: 0,
+                ours_end: 1,
+                theirs_start: 0,
+                theirs_end: 1,
+            },
+            MergeRegion {
+                tag: MergeTag::Both,
+                base_start: 1,
+                base_end: 2,
+                ours_start: 1,
+                ours_end: 2,
+                theirs_start: 1,
+                theirs_end: 2,
+            },
+            MergeRegion {
+                tag: MergeTag::Unchanged,
+                base_start: 2,
+                base_end: 3,
+                ours_start: 2,
+                ours_end: 3,
+                theirs_start: 2,
+                theirs_end: 3,
+            },
+            MergeRegion {
+                tag: MergeTag::Theirs,
+                base_start: 3,
+                base_end: 4,
+                ours_start: 3,
+                ours_end: 4,
+                theirs_start: 3,
+                theirs_end: 4,
+            },
+            MergeRegion {
+                tag: MergeTag::Unchanged,
+                base_start: 4,
+                base_end: 5,
+                ours_start: 4,
+                ours_end: 5,
+                theirs_start: 4,
+                theirs_end: 5,
+            },
+            MergeRegion {
+                tag: MergeTag::Conflict,
+                base_start: 5,
+                base_end: 6,
+                ours_start: 5,
+                ours_end: 6,
+                theirs_start: 5,
+                theirs_end: 6,
+            },
+            MergeRegion {
+                tag: MergeTag::Unchanged,
+                base_start: 6,
+                base_end: 7,
+                ours_start: 6,
+                ours_end: 7,
+                theirs_start: 6,
+                theirs_end: 7,
+            },
+            MergeRegion {
+                tag: MergeTag::Ours,
+                base_start: 7,
+                base_end: 7,
+                ours_start: 7,
+                ours_end: 8,
+                theirs_start: 7,
+                theirs_end: 7,
+            },
+        ];
+        assert_eq!(regions, expected);
+        let regions = merge3(&base, &ours, &theirs);
+        assert!(regions.iter().all(|r| r.tag != MergeTag::Conflict));
+        let merged = regions
+            .iter()
+            .flat_map(|region| match region.tag {
+                MergeTag::Theirs => {
+                    &theirs[region.theirs_start..region.theirs_end]
+                }
+                _ => &ours[region.ours_start..region.ours_end],
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        let expected =
+            vec!["1", "two", "3", "four", "5", "six", "7", "8"];
+        assert_eq!(merged, expected);
+    }
+
+    #[test]
+    fn t46() {
+        let base = "a\nb";
+        let ours = "a\nours";
+        let theirs = "a\ntheirs";
+        let (text, conflicts) =
+            merge3_text(base, ours, theirs, "HEAD", "base", "feature");
+        assert_eq!(conflicts, 1);
+        let expected =
+            "a\n<<<<<<< HEAD\nours\n||||||| base\nb\n=======\n\
+             theirs\n>>>>>>> feature\n";
+        assert_eq!(text, expected);
+        let (text, conflicts) =
+            merge3_text(base, ours, base, "HEAD", "base", "feature");
+        assert_eq!(conflicts, 0);
+        assert_eq!(text, ours);
+        let (text, conflicts) = merge3_text("", "", "", "", "", "");
+        assert_eq!((text.as_str(), conflicts), ("", 0));
+    }
+
+    #[test]
+    fn t47() {
+        let a = "abcdefgh".chars().collect::<Vec<_>>();
+        let b = "xabdefYYh".chars().collect::<Vec<_>>();
+        for algorithm in &[Algorithm::SequenceMatcher, Algorithm::Myers] {
+            let spans = Differ::new(&a, &b).algorithm(*algorithm).spans();
+            let patch = Patch::new(&a, &b, &spans);
+            assert!(!patch.is_empty());
+            assert_eq!(patch.apply(&a), Ok(b.clone()));
+        }
+        let spans = Differ::new(&a, &b).spans();
+        let patch = Patch::new(&a, &b, &spans);
+        assert_eq!(
+            patch.edits[0],
+            Edit {
+                span: Span::insert(0, 0, 0, 1),
+                deleted: vec![],
+                inserted: vec!['x']
+            }
+        );
+        assert_eq!(
+            patch.apply(&a[1..]),
+            Err(PatchError::WrongLength { expected: 8, actual: 7 })
+        );
+        let c = "abcdefZh".chars().collect::<Vec<_>>();
+        let error = patch.apply(&c).unwrap_err();
+        assert_eq!(error, PatchError::Mismatch { a_start: 6 });
+        assert_eq!(
+            error.to_string(),
+            "the sequence differs from the patch at 6"
+        );
+        let mut bad = patch.clone();
+        bad.edits.swap(0, 1);
+        assert_eq!(bad.apply(&a), Err(PatchError::Malformed));
+        let empty = Patch::new(&a, &a, &Differ::new(&a, &a).spans());
+        assert!(empty.is_empty());
+        assert_eq!(empty.apply(&a), Ok(a.clone()));
+    }
+
+    #[test]
+    fn t48() {
+        let a = (1..=20).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mut b = a.clone();
+        b[4] = "five".to_string();
+        b[11] = "twelve".to_string();
+        b[17] = "eighteen".to_string();
+        let hunks = Differ::new(&a, &b)
+            .grouped_spans(2)
+            .iter()
+            .map(|hunk| PatchHunk::new(&a, &b, hunk))
+            .collect::<Vec<_>>();
+        assert_eq!(hunks.len(), 3);
+        assert_eq!((hunks[0].a_len(), hunks[0].b_len()), (5, 5));
+        assert_eq!(apply_hunks(&a, &hunks, 0).0, b);
+        // Stale base: the first two lines removed, the line before the
+        // twelfth changed, and the eighteenth line changed.
+        let mut stale = a.clone();
+        stale.drain(0..2);
+        stale[8] = "eleven".to_string();
+        stale[15] = "EIGHTEEN".to_string();
+        let (patched, results) = apply_hunks(&stale, &hunks, 0);
+        assert_eq!(
+            results,
+            vec![
+                HunkResult::Applied { offset: -2, fuzz: 0 },
+                HunkResult::Failed,
+                HunkResult::Failed
+            ]
+        );
+        assert_eq!(patched[2], "five");
+        let (patched, results) = apply_hunks(&stale, &hunks, 2);
+        assert_eq!(
+            results,
+            vec![
+                HunkResult::Applied { offset: -2, fuzz: 0 },
+                HunkResult::Applied { offset: -2, fuzz: 2 },
+                HunkResult::Failed
+            ]
+        );
+        let mut expected = stale.clone();
+        expected[2] = "five".to_string();
+        expected[9] = "twelve".to_string();
+        assert_eq!(patched, expected);
+        let insert = PatchHunk {
+            a_start: 0,
+            b_start: 0,
+            lines: vec![PatchLine::Insert("0".to_string())],
+        };
+        let (patched, results) = apply_hunks(&stale[..1], &[insert], 2);
+        assert_eq!(patched, ["0", "3"]);
+        assert_eq!(results, [HunkResult::Applied { offset: 0, fuzz: 0 }]);
+    }
+
+    #[test]
+    fn t49() {
+        let diff = "From: someone\n\
+                    Subject: a patch\n\
+                    \n\
+                    diff --git a/a.txt b/a.txt\n\
+                    index 4cb29ea..047ece5 100644\n\
+                    --- a/a.txt\n\
+                    +++ b/a.txt\n\
+                    @@ -1,3 +1,4 @@\n \
+                    one\n\
+                    -two\n\
+                    +2\n \
+                    three\n\
+                    +four\n\
+                    \\ No newline at end of file\n\
+                    diff --git a/m.sh b/m.sh\n\
+                    old mode 100644\n\
+                    new mode 100755\n\
+                    diff --git a/r.txt b/s.txt\n\
+                    similarity index 100%\n\
+                    rename from r.txt\n\
+                    rename to s.txt\n";
+        let files = parse_unified_diff(diff).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].from_file, "a/a.txt");
+        assert_eq!(
+            files[0].header_value("index"),
+            Some("4cb29ea..047ece5 100644")
+        );
+        let hunk = &files[0].hunks[0];
+        assert_eq!((hunk.a_start, hunk.a_len()), (0, 3));
+        assert_eq!((hunk.b_start, hunk.b_len()), (0, 4));
+        assert_eq!(hunk.lines[4], PatchLine::Insert("four".to_string()));
+        assert_eq!(
+            hunk.hunk().spans,
+            vec![
+                Span::equal(0, 1, 0, 1),
+                Span::replace(1, 2, 1, 2),
+                Span::equal(2, 3, 2, 3),
+                Span::insert(3, 3, 3, 4),
+            ]
+        );
+        let a = ["one\n", "two\n", "three\n"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        let (b, _) = apply_hunks(&a, &files[0].hunks, 0);
+        assert_eq!(b.concat(), "one\n2\nthree\nfour");
+        assert_eq!(files[1].header_value("new mode"), Some("100755"));
+        assert!(files[1].hunks.is_empty());
+        assert_eq!(files[2].header_value("rename from"), Some("r.txt"));
+        assert_eq!(files[2].header_value("copy from"), None);
+        assert_eq!(files[2].from_file, "");
+        let rendered =
+            files.iter().map(|f| f.to_string()).collect::<String>();
+        assert_eq!(rendered, diff[diff.find("diff").unwrap()..]);
+    }
+
+    #[test]
+    fn t50() {
+        let a = "one\ntwo\n";
+        let b = "zero\none\n";
+        let diff = unified_diff(a, b, "a", "b", "", "", 3);
+        let files = parse_unified_diff(&diff).unwrap();
+        assert_eq!(files[0].to_string(), diff);
+        assert_eq!(files[0].to_file, "b");
+        let diff = "--- a\t2020-01-01\n+++ b\n@@ -0,0 +1 @@\n+new\n";
+        let files = parse_unified_diff(diff).unwrap();
+        assert_eq!(files[0].from_date, "2020-01-01");
+        assert_eq!(
+            files[0].hunks[0].hunk().spans,
+            [Span::insert(0, 0, 0, 1)]
+        );
+        assert_eq!(files[0].to_string(), diff);
+        let error =
+            parse_unified_diff("--- a\n+++ b\n@@ -1,2 +1 @@\n x\n")
+                .unwrap_err();
+        assert_eq!(error.to_string(), "line 5: incomplete hunk");
+        let error = parse_unified_diff("@@ -1 +1 @@\n").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError {
+                line: 1,
+                message: "hunk without a file".to_string()
+            }
+        );
+        let error = parse_unified_diff("--- a\n+++ b\n@@ -1 +x @@\n")
+            .unwrap_err();
+        assert_eq!(error.line, 3);
+        let error = parse_unified_diff("--- a\n+++ b\n@@ -1 +1 @@\n?x\n")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "line 4: invalid hunk line");
+    }
+
+    #[test]
+    fn t51() {
+        let texts = [
+            "",
+            "a",
+            "abc",
+            "abcabba",
+            "cbabac",
+            "xyz",
+            "aaaa",
+            "abcdef",
+            "fedcba",
+            "the quick brown fox",
+        ];
+        let algorithms = [
+            Algorithm::SequenceMatcher,
+            Algorithm::Myers,
+            Algorithm::Patience,
+            Algorithm::Histogram,
+        ];
+        for a in &texts {
+            for b in &texts {
+                let a = a.chars().collect::<Vec<_>>();
+                let b = b.chars().collect::<Vec<_>>();
+                for algorithm in &algorithms {
+                    let spans =
+                        Differ::new(&a, &b).algorithm(*algorithm).spans();
+                    let inverted = invert_spans(&spans);
+                    assert_eq!(invert_spans(&inverted), spans);
+                    for span in &inverted {
+                        assert_eq!(
+                            span.tag == Tag::Equal,
+                            b[span.a_start..span.a_end]
+                                == a[span.b_start..span.b_end]
+                        );
+                    }
+                    let patch = Patch::new(&a, &b, &spans);
+                    let patched = patch.apply(&a).unwrap();
+                    assert_eq!(
+                        patch.invert().apply(&patched),
+                        Ok(a.clone())
+                    );
+                    assert_eq!(
+                        patch.invert(),
+                        Patch::new(&b, &a, &inverted)
+                    );
+                    let hunks = hunks_for_spans(&spans, 1)
+                        .iter()
+                        .map(|hunk| PatchHunk::new(&a, &b, hunk).invert())
+                        .collect::<Vec<_>>();
+                    assert_eq!(apply_hunks(&b, &hunks, 0).0, a);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn t52() {
+        let diff = "diff --git a/a.txt b/a.txt\n\
+                    index 4cb29ea..047ece5 100644\n\
+                    --- a/a.txt\n\
+                    +++ b/a.txt\n\
+                    @@ -1,3 +1,4 @@\n \
+                    one\n\
+                    -two\n\
+                    +2\n \
+                    three\n\
+                    +four\n\
+                    \\ No newline at end of file\n\
+                    diff --git a/m.sh b/m.sh\n\
+                    old mode 100644\n\
+                    new mode 100755\n\
+                    diff --git a/r.txt b/s.txt\n\
+                    similarity index 100%\n\
+                    rename from r.txt\n\
+                    rename to s.txt\n";
+        // As output by git diff -R.
+        let expected = "diff --git b/a.txt a/a.txt\n\
+                        index 047ece5..4cb29ea 100644\n\
+                        --- b/a.txt\n\
+                        +++ a/a.txt\n\
+                        @@ -1,4 +1,3 @@\n \
+                        one\n\
+                        -2\n\
+                        +two\n \
+                        three\n\
+                        -four\n\
+                        \\ No newline at end of file\n\
+                        diff --git b/m.sh a/m.sh\n\
+                        old mode 100755\n\
+                        new mode 100644\n\
+                        diff --git b/s.txt a/r.txt\n\
+                        similarity index 100%\n\
+                        rename from s.txt\n\
+                        rename to r.txt\n";
+        let files = parse_unified_diff(diff).unwrap();
+        let inverted =
+            files.iter().map(|file| file.invert()).collect::<Vec<_>>();
+        let rendered =
+            inverted.iter().map(|f| f.to_string()).collect::<String>();
+        assert_eq!(rendered, expected);
+        let file = parse_unified_diff(
+            "diff --git a/new b/new\nnew file mode 100644\n\
+             --- /dev/null\n+++ b/new\n@@ -0,0 +1 @@\n+x\n",
+        )
+        .unwrap()
+        .remove(0)
+        .invert();
+        assert_eq!(
+            file.to_string(),
+            "diff --git b/new a/new\ndeleted file mode 100644\n\
+             --- b/new\n+++ /dev/null\n@@ -1 +0,0 @@\n-x\n"
+        );
+        // As output by git diff -M and git diff -M -R for the same two
+        // commits.
+        let diff = "diff --git a/added.txt b/added.txt\n\
+                    new file mode 100644\n\
+                    index 0000000..587be6b\n\
+                    --- /dev/null\n\
+                    +++ b/added.txt\n\
+                    @@ -0,0 +1 @@\n\
+                    +x\n\
+                    diff --git a/gone.txt b/gone.txt\n\
+                    deleted file mode 100644\n\
+                    index 2fa992c..0000000\n\
+                    --- a/gone.txt\n\
+                    +++ /dev/null\n\
+                    @@ -1 +0,0 @@\n\
+                    -keep\n\
+                    diff --git a/m.sh b/m.sh\n\
+                    old mode 100644\n\
+                    new mode 100755\n\
+                    index 7898192..6178079\n\
+                    --- a/m.sh\n\
+                    +++ b/m.sh\n\
+                    @@ -1 +1 @@\n\
+                    -a\n\
+                    +b\n\
+                    diff --git a/old.txt b/new.txt\n\
+                    similarity index 71%\n\
+                    rename from old.txt\n\
+                    rename to new.txt\n\
+                    index 4cb29ea..f04eb26 100644\n\
+                    --- a/old.txt\n\
+                    +++ b/new.txt\n\
+                    @@ -1,3 +1,3 @@\n \
+                    one\n\
+                    -two\n\
+                    +2\n \
+                    three\n";
+        let expected = "diff --git b/added.txt a/added.txt\n\
+                        deleted file mode 100644\n\
+                        index 587be6b..0000000\n\
+                        --- b/added.txt\n\
+                        +++ /dev/null\n\
+                        @@ -1 +0,0 @@\n\
+                        -x\n\
+                        diff --git b/gone.txt a/gone.txt\n\
+                        new file mode 100644\n\
+                        index 0000000..2fa992c\n\
+                        --- /dev/null\n\
+                        +++ a/gone.txt\n\
+                        @@ -0,0 +1 @@\n\
+                        +keep\n\
+                        diff --git b/m.sh a/m.sh\n\
+                        old mode 100755\n\
+                        new mode 100644\n\
+                        index 6178079..7898192\n\
+                        --- b/m.sh\n\
+                        +++ a/m.sh\n\
+                        @@ -1 +1 @@\n\
+                        -b\n\
+                        +a\n\
+                        diff --git b/new.txt a/old.txt\n\
+                        similarity index 71%\n\
+                        rename from new.txt\n\
+                        rename to old.txt\n\
+                        index f04eb26..4cb29ea 100644\n\
+                        --- b/new.txt\n\
+                        +++ a/old.txt\n\
+                        @@ -1,3 +1,3 @@\n \
+                        one\n\
+                        -2\n\
+                        +two\n \
+                        three\n";
+        let rendered = parse_unified_diff(diff)
+            .unwrap()
+            .iter()
+            .map(|file| file.invert().to_string())
+            .collect::<String>();
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn t53() {
+        let texts = [
+            "", "a", "abc", "abcabba", "cbabac", "xyz", "aaaa", "fedcba",
+        ];
+        for a in &texts {
+            for b in &texts {
+                for c in &texts {
+                    let a = a.chars().collect::<Vec<_>>();
+                    let b = b.chars().collect::<Vec<_>>();
+                    let c = c.chars().collect::<Vec<_>>();
+                    let ab = Differ::new(&a, &b).spans();
+                    let bc = Differ::new(&b, &c)
+                        .algorithm(Algorithm::Myers)
+                        .spans();
+                    let ac = compose_spans(&ab, &bc);
+                    let mut i = 0;
+                    let mut k = 0;
+                    for span in &ac {
+                        assert_eq!((span.a_start, span.b_start), (i, k));
+                        assert!(
+                            span.a_start < span.a_end
+                                || span.b_start < span.b_end
+                        );
+                        if span.tag == Tag::Equal {
+                            assert_eq!(
+                                a[span.a_start..span.a_end],
+                                c[span.b_start..span.b_end]
+                            );
+                        }
+                        i = span.a_end;
+                        k = span.b_end;
+                    }
+                    assert_eq!((i, k), (a.len(), c.len()));
+                    let ab = Patch::new(&a, &b, &ab);
+                    let bc = Patch::new(&b, &c, &bc);
+                    let patch = ab.compose(&bc).unwrap();
+                    assert_eq!(patch.spans(), ac);
+                    assert_eq!(patch.apply(&a), Ok(c.clone()));
+                    assert_eq!(patch.invert().apply(&c), Ok(a.clone()));
+                }
+            }
+        }
+        let a = "abcd".chars().collect::<Vec<_>>();
+        let b = "aXd".chars().collect::<Vec<_>>();
+        let spans = Differ::new(&a, &b).spans();
+        let patch = Patch::new(&a, &b, &spans);
+        assert_eq!(
+            patch.compose(&patch),
+            Err(PatchError::WrongLength { expected: 3, actual: 4 })
+        );
+        let undo = patch.compose(&patch.invert()).unwrap();
+        assert_eq!(
+            undo.spans(),
+            compose_spans(&spans, &invert_spans(&spans))
+        );
+        assert_eq!(
+            undo.spans(),
+            vec![
+                Span::equal(0, 1, 0, 1),
+                Span::replace(1, 3, 1, 3),
+                Span::equal(3, 4, 3, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn t54() {
+        let a = ["1", "2", "3", "4", "5", "6"];
+        let b = ["1", "2", "new", "3", "5", "6", "7"];
+        let matches = Differ::new(&a, &b).matches();
+        let map = PositionMap::new(&spans_for_matches(&matches));
+        let a_to_b = |snap| {
+            (0..=a.len() + 1)
+                .map(|i| map.a_to_b(i, snap))
+                .collect::<Vec<_>>()
+        };
+        let mut expected = vec![
+            Some(0),
+            Some(1),
+            Some(3),
+            Some(4),
+            Some(4),
+            Some(5),
+            Some(7),
+            None,
+        ];
+        assert_eq!(a_to_b(Snap::Left), expected);
+        assert_eq!(a_to_b(Snap::Right), expected);
+        expected[3] = None;
+        assert_eq!(a_to_b(Snap::Gone), expected);
+        assert_eq!(map.b_to_a(2, Snap::Gone), None);
+        assert_eq!(map.b_to_a(2, Snap::Left), Some(2));
+        assert_eq!(map.b_to_a(6, Snap::Left), Some(6));
+        assert_eq!(map.b_to_a(7, Snap::Gone), Some(6));
+        assert_eq!(map.a_range_to_b(1..3, Snap::Gone), Some(1..4));
+        assert_eq!(map.a_range_to_b(2..4, Snap::Gone), None);
+        assert_eq!(map.a_range_to_b(2..4, Snap::Left), Some(3..4));
+        assert_eq!(map.a_range_to_b(3..4, Snap::Right), Some(4..4));
+        assert_eq!(map.a_range_to_b(6..6, Snap::Gone), Some(7..7));
+        assert_eq!(map.b_range_to_a(1..4, Snap::Gone), Some(1..3));
+        assert_eq!(map.b_range_to_a(1..7, Snap::Right), Some(1..6));
+        assert_eq!(map.b_range_to_a(2..3, Snap::Right), Some(2..2));
+        let empty = PositionMap::new(&[]);
+        assert_eq!(empty.a_to_b(0, Snap::Gone), Some(0));
+        assert_eq!(empty.b_to_a(1, Snap::Left), None);
+    }
+
+    #[test]
+    fn t55() {
+        let a = "fn f() {\n    one();\n    two();\n}\n\n\
+                 fn g() {\n    three();\n    four();\n    five();\n}\n\
+                 // end\n";
+        let b = "fn g() {\n    three();\n    four();\n    five();\n}\n\
+                 // END\n\n\
+                 fn f() {\n    one();\n    TWO();\n}\n";
+        let a = a.split_inclusive('\n').collect::<Vec<_>>();
+        let b = b.split_inclusive('\n').collect::<Vec<_>>();
+        let spans = Differ::new(&a, &b).spans();
+        assert_eq!(
+            spans,
+            vec![
+                Span::delete(0, 5, 0, 0),
+                Span::equal(5, 10, 0, 5),
+                Span::replace(10, 11, 5, 11),
+            ]
+        );
+        // f() was changed as well as moved.
+        assert!(detect_moves(&a, &b, &spans, 1.0, 1).is_empty());
+        let moves = detect_moves(&a, &b, &spans, 0.5, 2);
+        assert_eq!(moves.len(), 1);
+        let m = &moves[0];
+        assert_eq!(
+            (m.a_start, m.a_end, m.b_start, m.b_end),
+            (0, 5, 5, 11)
+        );
+        assert!((m.ratio - 6.0 / 11.0).abs() < 1e-9);
+        assert!(detect_moves(&a, &b, &spans, 0.5, 6).is_empty());
+        let a = ["x", "y", "p", "q", "r"];
+        let b = ["p", "q", "r", "x", "y", "x"];
+        let spans = Differ::new(&a, &b).spans();
+        let moves = detect_moves(&a, &b, &spans, 0.5, 1);
+        assert_eq!(
+            moves,
+            vec![Move {
+                a_start: 0,
+                a_end: 2,
+                b_start: 3,
+                b_end: 6,
+                ratio: 0.8
+            }]
+        );
+    }
+
+    #[test]
+    fn t56() {
+        let spans = vec![
+            Span::delete(0, 1, 0, 0),
+            Span::equal(1, 2, 0, 1),
+            Span::delete(2, 3, 1, 1),
+        ];
+        assert_eq!(
+            cleanup_semantic(b"abc", b"b", &spans),
+            vec![Span::replace(0, 3, 0, 1)]
+        );
+        let spans = vec![
+            Span::delete(0, 2, 0, 0),
+            Span::equal(2, 4, 0, 2),
+            Span::delete(4, 5, 2, 2),
+            Span::equal(5, 6, 2, 3),
+            Span::insert(6, 6, 3, 4),
+        ];
+        assert_eq!(
+            cleanup_semantic(b"abcdef", b"cdfg", &spans),
+            vec![Span::replace(0, 6, 0, 4)]
+        );
+        let spans = vec![
+            Span::replace(0, 2, 0, 2),
+            Span::equal(2, 4, 2, 4),
+            Span::delete(4, 5, 4, 4),
+        ];
+        assert_eq!(cleanup_semantic(b"ab12e", b"cd12", &spans), spans);
+        // Overlaps
+        let spans = vec![Span::replace(0, 6, 0, 6)];
+        assert_eq!(
+            cleanup_semantic(b"abcxxx", b"xxxdef", &spans),
+            vec![
+                Span::delete(0, 3, 0, 0),
+                Span::equal(3, 6, 0, 3),
+                Span::insert(6, 6, 3, 6),
+            ]
+        );
+        assert_eq!(
+            cleanup_semantic(b"xxxabc", b"defxxx", &spans),
+            vec![
+                Span::insert(0, 0, 0, 3),
+                Span::equal(0, 3, 3, 6),
+                Span::delete(3, 6, 6, 6),
+            ]
+        );
+        assert!(cleanup_semantic(b"", b"", &[]).is_empty());
+    }
+
+    #[test]
+    fn t57() {
+        // See t03
+        let a = b"private Thread currentThread;";
+        let b = b"private volatile Thread currentThread;";
+        let spans = Differ::new(&a[..], &b[..]).spans();
+        assert_eq!(
+            cleanup_semantic_lossless(&a[..], &b[..], &spans),
+            vec![
+                Span::equal(0, 8, 0, 8),
+                Span::insert(8, 8, 8, 17),
+                Span::equal(8, 29, 17, 38),
+            ]
+        );
+        let a = "The cat.".chars().collect::<Vec<_>>();
+        let b = "The cow and the cat.".chars().collect::<Vec<_>>();
+        let spans = vec![
+            Span::equal(0, 5, 0, 5),
+            Span::insert(5, 5, 5, 17),
+            Span::equal(5, 8, 17, 20),
+        ];
+        assert_eq!(
+            cleanup_semantic_lossless(&a, &b, &spans),
+            vec![
+                Span::equal(0, 4, 0, 4),
+                Span::insert(4, 4, 4, 16),
+                Span::equal(4, 8, 16, 20),
+            ]
+        );
+        // Blank lines beat line breaks
+        let a = b"AAA\r\n\r\nBBB\r\nEEE";
+        let b = b"AAA\r\n\r\nBBB\r\nDDD\r\n\r\nBBB\r\nEEE";
+        let spans = vec![
+            Span::equal(0, 10, 0, 10),
+            Span::insert(10, 10, 10, 22),
+            Span::equal(10, 15, 22, 27),
+        ];
+        assert_eq!(
+            cleanup_semantic_lossless(&a[..], &b[..], &spans),
+            vec![
+                Span::equal(0, 7, 0, 7),
+                Span::insert(7, 7, 7, 19),
+                Span::equal(7, 15, 19, 27),
+            ]
+        );
+        // A change that can't move is left alone
+        let spans = vec![Span::replace(0, 3, 0, 3)];
+        assert_eq!(
+            cleanup_semantic_lossless(b"abc", b"xyz", &spans),
+            spans
+        );
+    }
+
+    #[test]
+    fn t58() {
+        let spans = vec![
+            Span::replace(0, 2, 0, 2),
+            Span::equal(2, 5, 2, 5),
+            Span::replace(5, 7, 5, 7),
+        ];
+        assert_eq!(
+            cleanup_efficiency(b"abxyzcd", b"12xyz34", &spans, 4),
+            vec![Span::replace(0, 7, 0, 7)]
+        );
+        // Three of the four sides changed
+        let spans = vec![
+            Span::insert(0, 0, 0, 2),
+            Span::equal(0, 1, 2, 3),
+            Span::replace(1, 3, 3, 5),
+        ];
+        assert_eq!(
+            cleanup_efficiency(b"xcd", b"12x34", &spans, 4),
+            vec![Span::replace(0, 3, 0, 5)]
+        );
+        assert_eq!(
+            cleanup_efficiency(b"xcd", b"12x34", &spans, 2),
+            spans
+        );
+        // The cost is the only difference between the two cleanups
+        let a = "the cat sat".chars().collect::<Vec<_>>();
+        let b = "a dog sat".chars().collect::<Vec<_>>();
+        let spans = Differ::new(&a, &b).spans();
+        assert!(
+            cleanup_efficiency(&a, &b, &spans, 4).len() < spans.len()
+        );
+        assert_eq!(cleanup_efficiency(&a, &b, &spans, 0), spans);
+    }
+
+    #[test]
+    fn t59() {
+        let a = ["{\n", "    x();\n", "}\n", "{\n", "    y();\n", "}\n"];
+        let b = ["{\n", "    y();\n", "}\n"];
+        let spans = vec![
+            Span::equal(0, 1, 0, 1),
+            Span::delete(1, 4, 1, 1), // "    x();", "}", "{"
+            Span::equal(4, 6, 1, 3),
+        ];
+        assert_eq!(
+            slide_spans(&a, &b, &spans),
+            vec![Span::delete(0, 3, 0, 0), Span::equal(3, 6, 0, 3)]
+        );
+        // A block can be slid right to the end
+        let a = ["x\n", "\n", "if y:\n", "    z\n"];
+        let b = [
+            "x\n", "\n", "if y:\n", "    z\n", "\n", "if y:\n", "    z\n",
+        ];
+        let spans = vec![
+            Span::equal(0, 2, 0, 2),
+            Span::insert(2, 2, 2, 5), // "if y:", "    z", ""
+            Span::equal(2, 4, 5, 7),
+        ];
+        assert_eq!(
+            slide_spans(&a, &b, &spans),
+            vec![Span::equal(0, 4, 0, 4), Span::insert(4, 4, 4, 7)]
+        );
+        // Nothing to slide
+        let a = ["a\n", "b\n", "c\n"];
+        let b = ["a\n", "c\n"];
+        let spans = Differ::new(&a, &b).spans();
+        assert_eq!(slide_spans(&a, &b, &spans), spans);
+        let spans = vec![Span::replace(0, 3, 0, 2)];
+        assert_eq!(slide_spans(&a, &b, &spans), spans);
+    }
+
+    #[test]
+    fn t60() {
+        assert_eq!(levenshtein(b"kitten", b"sitting"), 3);
+        assert_eq!(levenshtein(b"", b"abc"), 3);
+        assert_eq!(levenshtein(b"abc", b""), 3);
+        assert_eq!(levenshtein::<u8>(b"", b""), 0);
+        assert_eq!(levenshtein(b"ab", b"ba"), 2);
+        assert_eq!(damerau_levenshtein(b"ab", b"ba"), 1);
+        assert_eq!(damerau_levenshtein(b"abcdef", b"badcfe"), 3);
+        // Optimal string alignment doesn't edit a transposed pair again
+        assert_eq!(damerau_levenshtein(b"ca", b"abc"), 3);
+        let a = ["let", "x", "=", "1"];
+        let b = ["let", "mut", "x", "=", "2"];
+        let distance = EditDistance::new(&a, &b);
+        assert_eq!(distance.distance(), Some(2));
+        assert_eq!(
+            distance.spans(),
+            Some(vec![
+                Span::equal(0, 1, 0, 1),
+                Span::insert(1, 1, 1, 2),
+                Span::equal(1, 3, 2, 4),
+                Span::replace(3, 4, 4, 5),
+            ])
+        );
+        let costs =
+            EditCosts { insert: 2, delete: 3, ..EditCosts::default() };
+        let distance = EditDistance::new(&a, &b).costs(costs);
+        assert_eq!(distance.distance(), Some(3));
+        assert_eq!(
+            EditDistance::new(&b, &a).costs(costs).distance(),
+            Some(4)
+        );
+        // Bounded
+        let a = b"identifier";
+        let b = b"identfiier";
+        let distance = EditDistance::new(&a[..], &b[..]);
+        assert_eq!(distance.distance(), Some(2));
+        let distance = distance.transpositions(true).max_distance(1);
+        assert_eq!(distance.distance(), Some(1));
+        assert_eq!(
+            distance.spans(),
+            Some(vec![
+                Span::equal(0, 5, 0, 5),
+                Span::replace(5, 7, 5, 7),
+                Span::equal(7, 10, 7, 10),
+            ])
+        );
+        let distance = distance.max_distance(0);
+        assert_eq!(distance.distance(), None);
+        assert_eq!(distance.spans(), None);
+        let distance = EditDistance::new(&a[..], b"id").max_distance(7);
+        assert_eq!(distance.distance(), None);
+        assert_eq!(distance.max_distance(8).distance(), Some(8));
+    }
 }
@@ -0,0 +1,255 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::structs::{Span, Tag};
+
+/// Returns the spans that convert sequence `b` into sequence `a`, given
+/// the spans that convert `a` into `b`.
+///
+/// Each span's `a` and `b` ranges are swapped, and `Tag::Insert`s become
+/// `Tag::Delete`s and vice versa, so there is no need to recompute the
+/// differences, e.g., to undo a change.
+///
+/// ```
+/// use differ::{invert_spans, Differ, Span};
+///
+/// let a = ["one", "two", "three"];
+/// let b = ["one", "three", "four"];
+/// let spans = Differ::new(&a, &b).spans();
+/// assert_eq!(
+///     invert_spans(&spans),
+///     vec![
+///         Span::equal(0, 1, 0, 1),
+///         Span::insert(1, 1, 1, 2),
+///         Span::equal(1, 2, 2, 3),
+///         Span::delete(2, 3, 3, 3),
+///     ]
+/// );
+/// assert_eq!(invert_spans(&spans), Differ::new(&b, &a).spans());
+/// ```
+pub fn invert_spans(spans: &[Span]) -> Vec<Span> {
+    spans.iter().map(invert_span).collect()
+}
+
+pub(crate) fn invert_span(span: &Span) -> Span {
+    Span {
+        tag: match span.tag {
+            Tag::Insert => Tag::Delete,
+            Tag::Delete => Tag::Insert,
+            ref tag => tag.clone(),
+        },
+        a_start: span.b_start,
+        a_end: span.b_end,
+        b_start: span.a_start,
+        b_end: span.a_end,
+    }
+}
+
+/// Returns the spans that convert sequence `a` into sequence `c`, given
+/// the spans that convert `a` into `b` and those that convert `b` into
+/// `c`.
+///
+/// This is much cheaper than comparing `a` and `c`, and so is useful
+/// for collapsing a chain of edits. However, the result is not always
+/// minimal, e.g., if an item is deleted by the first change and
+/// reinserted by the second, it is shown as replaced rather than equal.
+///
+/// # Panics
+///
+/// Panics if the spans' `b` sequences have different lengths.
+///
+/// ```
+/// use differ::{compose_spans, Differ, Span};
+///
+/// let a = ["a", "b", "c"];
+/// let b = ["a", "B", "c"];
+/// let c = ["a", "B", "c", "d"];
+/// let ab = Differ::new(&a, &b).spans();
+/// let bc = Differ::new(&b, &c).spans();
+/// assert_eq!(
+///     compose_spans(&ab, &bc),
+///     vec![
+///         Span::equal(0, 1, 0, 1),
+///         Span::replace(1, 2, 1, 2),
+///         Span::equal(2, 3, 2, 3),
+///         Span::insert(3, 3, 3, 4),
+///     ]
+/// );
+/// ```
+pub fn compose_spans(ab: &[Span], bc: &[Span]) -> Vec<Span> {
+    let mut spans: Vec<Span> = vec![];
+    for piece in compose_pieces(ab, bc) {
+        let (a_len, c_len) = match piece.kind {
+            Piece::Equal => (piece.len, piece.len),
+            Piece::AbDelete | Piece::BcDelete => (piece.len, 0),
+            Piece::AbInsert | Piece::BcInsert => (0, piece.len),
+        };
+        push_run(&mut spans, piece.kind == Piece::Equal, a_len, c_len);
+    }
+    spans
+}
+
+/// Appends a run of `a_len` items of `a` and `b_len` items of `b` to the
+/// spans, extending the last span if it is of the same kind (equal or
+/// changed).
+pub(crate) fn push_run(
+    spans: &mut Vec<Span>,
+    equal: bool,
+    a_len: usize,
+    b_len: usize,
+) {
+    match spans.last_mut() {
+        Some(span) if (span.tag == Tag::Equal) == equal => {
+            span.a_end += a_len;
+            span.b_end += b_len;
+            if !equal {
+                span.tag = change_tag(span);
+            }
+        }
+        _ => {
+            let (a_start, b_start) = spans
+                .last()
+                .map_or((0, 0), |span| (span.a_end, span.b_end));
+            let mut span = Span::equal(
+                a_start,
+                a_start + a_len,
+                b_start,
+                b_start + b_len,
+            );
+            if !equal {
+                span.tag = change_tag(&span);
+            }
+            spans.push(span);
+        }
+    }
+}
+
+fn change_tag(span: &Span) -> Tag {
+    match (span.a_start < span.a_end, span.b_start < span.b_end) {
+        (true, true) => Tag::Replace,
+        (true, false) => Tag::Delete,
+        _ => Tag::Insert,
+    }
+}
+
+/// Where a piece of a composed a→c change comes from.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Piece {
+    /// Equal in both changes.
+    Equal,
+    /// Deleted by the a→b change.
+    AbDelete,
+    /// Equal in the a→b change and deleted by the b→c change.
+    BcDelete,
+    /// Inserted by the a→b change and equal in the b→c change.
+    AbInsert,
+    /// Inserted by the b→c change.
+    BcInsert,
+}
+
+/// A run of `len` items starting at `a`, `b`, and `c` in each of the
+/// sequences.
+pub(crate) struct ComposedPiece {
+    pub(crate) kind: Piece,
+    pub(crate) a: usize,
+    pub(crate) b: usize,
+    pub(crate) c: usize,
+    pub(crate) len: usize,
+}
+
+// Returns the pieces of the a→c change in order; items that are inserted
+// by the a→b change and deleted by the b→c change are omitted.
+pub(crate) fn compose_pieces(
+    ab: &[Span],
+    bc: &[Span],
+) -> Vec<ComposedPiece> {
+    let ab_b_len = ab.last().map_or(0, |span| span.b_end);
+    let bc_a_len = bc.last().map_or(0, |span| span.a_end);
+    assert_eq!(ab_b_len, bc_a_len, "the b sequences' lengths differ");
+    let mut ab_runs = runs(ab).into_iter().peekable();
+    let mut bc_runs = runs(bc).into_iter().peekable();
+    let mut pieces = vec![];
+    let (mut a, mut b, mut c) = (0, 0, 0);
+    loop {
+        if let Some((Run::Delete, len)) = ab_runs.peek().copied() {
+            pieces.push(ComposedPiece {
+                kind: Piece::AbDelete,
+                a,
+                b,
+                c,
+                len,
+            });
+            a += len;
+            ab_runs.next();
+            continue;
+        }
+        if let Some((Run::Insert, len)) = bc_runs.peek().copied() {
+            pieces.push(ComposedPiece {
+                kind: Piece::BcInsert,
+                a,
+                b,
+                c,
+                len,
+            });
+            c += len;
+            bc_runs.next();
+            continue;
+        }
+        let (ab_run, bc_run) =
+            match (ab_runs.peek_mut(), bc_runs.peek_mut()) {
+                (Some(ab_run), Some(bc_run)) => (ab_run, bc_run),
+                _ => break,
+            };
+        let len = ab_run.1.min(bc_run.1);
+        let kind = match (ab_run.0, bc_run.0) {
+            (Run::Equal, Run::Equal) => Some(Piece::Equal),
+            (Run::Equal, _) => Some(Piece::BcDelete),
+            (_, Run::Equal) => Some(Piece::AbInsert),
+            _ => None,
+        };
+        ab_run.1 -= len;
+        bc_run.1 -= len;
+        if ab_run.1 == 0 {
+            ab_runs.next();
+        }
+        if bc_run.1 == 0 {
+            bc_runs.next();
+        }
+        if let Some(kind) = kind {
+            pieces.push(ComposedPiece { kind, a, b, c, len });
+            if kind != Piece::AbInsert {
+                a += len;
+            }
+            if kind != Piece::BcDelete {
+                c += len;
+            }
+        }
+        b += len;
+    }
+    pieces
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Run {
+    Equal,
+    Delete,
+    Insert,
+}
+
+// Returns the spans as runs of equal, deleted, and inserted items, with
+// each replacement split into a deletion and an insertion.
+fn runs(spans: &[Span]) -> Vec<(Run, usize)> {
+    let mut runs = vec![];
+    for span in spans {
+        let a_len = span.a_end - span.a_start;
+        let b_len = span.b_end - span.b_start;
+        match span.tag {
+            Tag::Equal => runs.push((Run::Equal, a_len)),
+            _ => {
+                runs.push((Run::Delete, a_len));
+                runs.push((Run::Insert, b_len));
+            }
+        }
+    }
+    runs.retain(|(_, len)| *len > 0);
+    runs
+}
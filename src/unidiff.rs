@@ -0,0 +1,355 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::differ::{push_header, push_lines, unified_range};
+use crate::patch::{PatchHunk, PatchLine};
+use std::error::Error;
+use std::fmt;
+
+/// Holds the changes to one file parsed from a unified diff by
+/// [`parse_unified_diff()`](fn.parse_unified_diff.html).
+///
+/// The `header` holds any lines that precede the `---` and `+++` lines,
+/// such as git's `diff --git`, `index`, mode, and rename lines, exactly
+/// as they were (including their line endings). The file names and
+/// dates are from the `---` and `+++` lines; they are empty if there
+/// were no such lines, e.g., for a git rename that has no changes.
+///
+/// Each hunk's lines keep their line endings, except for a line that was
+/// followed by `\ No newline at end of file`, which has none.
+///
+/// A `FilePatch` is rendered as a unified diff using `to_string()`
+/// (i.e., `Display`); hunk headings are regenerated from the hunks, so
+/// any section headings (such as function names) after the closing `@@`
+/// are not kept.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FilePatch {
+    pub header: Vec<String>,
+    pub from_file: String,
+    pub from_date: String,
+    pub to_file: String,
+    pub to_date: String,
+    pub hunks: Vec<PatchHunk<String>>,
+}
+
+impl FilePatch {
+    /// Returns the value of the header line that starts with `name`
+    /// followed by a space, e.g., `header_value("rename from")` or
+    /// `header_value("new mode")`.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.header.iter().find_map(|line| {
+            line.strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix(' '))
+                .map(|value| value.trim_end_matches(&['\n', '\r'][..]))
+        })
+    }
+    /// Returns the patch that converts the new file back into the old
+    /// one, i.e., that undoes this patch (like `git diff -R`).
+    ///
+    /// The file names, dates, and hunks are swapped or inverted, and so
+    /// are the `diff --git` line's paths and any rename, mode, new and
+    /// deleted file, and index header lines.
+    pub fn invert(&self) -> Self {
+        let mut header = self
+            .header
+            .iter()
+            .map(|line| invert_header_line(line))
+            .collect::<Vec<_>>();
+        for (old, new) in
+            &[("rename from ", "rename to "), ("old mode ", "new mode ")]
+        {
+            let i = header.iter().position(|line| line.starts_with(old));
+            let j = header.iter().position(|line| line.starts_with(new));
+            if let (Some(i), Some(j)) = (i, j) {
+                let old_value = header[i][old.len()..].to_string();
+                let new_value = header[j][new.len()..].to_string();
+                header[i] = format!("{}{}", old, new_value);
+                header[j] = format!("{}{}", new, old_value);
+            }
+        }
+        FilePatch {
+            header,
+            from_file: self.to_file.clone(),
+            from_date: self.to_date.clone(),
+            to_file: self.from_file.clone(),
+            to_date: self.from_date.clone(),
+            hunks: self.hunks.iter().map(|hunk| hunk.invert()).collect(),
+        }
+    }
+}
+
+// Returns the header line with its old and new parts swapped, if it has
+// both; rename and mode lines are handled by FilePatch::invert().
+fn invert_header_line(line: &str) -> String {
+    if let Some(rest) = line.strip_prefix("new file mode ") {
+        return format!("deleted file mode {}", rest);
+    }
+    if let Some(rest) = line.strip_prefix("deleted file mode ") {
+        return format!("new file mode {}", rest);
+    }
+    if let Some(rest) = line.strip_prefix("diff --git ") {
+        let paths = rest.split_whitespace().collect::<Vec<_>>();
+        if let [old, new] = paths[..] {
+            let ending = &rest[rest.trim_end().len()..];
+            return format!("diff --git {} {}{}", new, old, ending);
+        }
+    }
+    if let Some(rest) = line.strip_prefix("index ") {
+        if let Some((old, rest)) = rest.split_once("..") {
+            let end = rest.find(|c: char| c.is_whitespace());
+            let (new, rest) = rest.split_at(end.unwrap_or(rest.len()));
+            return format!("index {}..{}{}", new, old, rest);
+        }
+    }
+    line.to_string()
+}
+
+impl fmt::Display for FilePatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut diff = String::new();
+        for line in &self.header {
+            diff.push_str(line);
+            if !line.ends_with('\n') {
+                diff.push('\n');
+            }
+        }
+        if !self.from_file.is_empty() || !self.to_file.is_empty() {
+            push_header(
+                &mut diff,
+                "---",
+                &self.from_file,
+                &self.from_date,
+            );
+            push_header(&mut diff, "+++", &self.to_file, &self.to_date);
+        }
+        for hunk in &self.hunks {
+            diff.push_str(&format!(
+                "@@ -{} +{} @@\n",
+                unified_range(hunk.a_start, hunk.a_start + hunk.a_len()),
+                unified_range(hunk.b_start, hunk.b_start + hunk.b_len())
+            ));
+            for line in &hunk.lines {
+                let (prefix, text) = match line {
+                    PatchLine::Context(text) => (" ", text),
+                    PatchLine::Delete(text) => ("-", text),
+                    PatchLine::Insert(text) => ("+", text),
+                };
+                push_lines(&mut diff, prefix, &[text]);
+            }
+        }
+        write!(f, "{}", diff)
+    }
+}
+
+/// Returns the files and hunks parsed from a unified diff (as produced
+/// by GNU `diff -u`, `git diff`, or
+/// [`unified_diff()`](fn.unified_diff.html)).
+///
+/// The diff may contain changes to any number of files. Lines that are
+/// not part of a diff (e.g., an email's text preceding a patch) are
+/// ignored. The hunks can be converted into spans using
+/// [`PatchHunk::hunk()`](struct.PatchHunk.html#method.hunk), or applied
+/// using [`apply_hunks()`](fn.apply_hunks.html).
+///
+/// ```
+/// use differ::{parse_unified_diff, PatchLine, Span};
+///
+/// let diff = "diff --git a/old.txt b/new.txt\n\
+///             similarity index 50%\n\
+///             rename from old.txt\n\
+///             rename to new.txt\n\
+///             --- a/old.txt\n\
+///             +++ b/new.txt\n\
+///             @@ -1,2 +1,2 @@\n \
+///              one\n\
+///             -two\n\
+///             +TWO\n\
+///             \\ No newline at end of file\n";
+/// let files = parse_unified_diff(diff).unwrap();
+/// assert_eq!(files[0].header_value("rename to"), Some("new.txt"));
+/// assert_eq!(files[0].to_file, "b/new.txt");
+/// let hunk = &files[0].hunks[0];
+/// assert_eq!(hunk.lines[2], PatchLine::Insert("TWO".to_string()));
+/// assert_eq!(hunk.hunk().spans[1], Span::replace(1, 2, 1, 2));
+/// assert_eq!(files[0].to_string(), diff);
+/// ```
+pub fn parse_unified_diff(
+    diff: &str,
+) -> Result<Vec<FilePatch>, ParseError> {
+    let lines = diff.split_inclusive('\n').collect::<Vec<_>>();
+    let mut files: Vec<FilePatch> = vec![];
+    let mut in_header = false; // after "diff" but before "---" or "@@"
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("diff ") {
+            files.push(FilePatch::default());
+            files.last_mut().unwrap().header.push(line.to_string());
+            in_header = true;
+            i += 1;
+        } else if line.starts_with("--- ")
+            && matches!(
+                lines.get(i + 1),
+                Some(line) if line.starts_with("+++ ")
+            )
+        {
+            if !in_header {
+                files.push(FilePatch::default());
+            }
+            let file = files.last_mut().unwrap();
+            let (name, date) = file_and_date(&line[4..]);
+            file.from_file = name;
+            file.from_date = date;
+            let (name, date) = file_and_date(&lines[i + 1][4..]);
+            file.to_file = name;
+            file.to_date = date;
+            in_header = false;
+            i += 2;
+        } else if line.starts_with("@@ ") {
+            let file = match files.last_mut() {
+                Some(file) => file,
+                None => {
+                    return Err(ParseError::new(i, "hunk without a file"))
+                }
+            };
+            let (hunk, next) = parse_hunk(&lines, i)?;
+            file.hunks.push(hunk);
+            i = next;
+            in_header = false;
+        } else {
+            if in_header {
+                files.last_mut().unwrap().header.push(line.to_string());
+            }
+            i += 1;
+        }
+    }
+    Ok(files)
+}
+
+// Returns the name and date (if any) from a "---" or "+++" line.
+fn file_and_date(text: &str) -> (String, String) {
+    let text = text.trim_end_matches(&['\n', '\r'][..]);
+    match text.split_once('\t') {
+        Some((name, date)) => (name.to_string(), date.to_string()),
+        None => (text.to_string(), String::new()),
+    }
+}
+
+// Returns the hunk whose "@@" line is lines[i] and the index of the line
+// following it.
+fn parse_hunk(
+    lines: &[&str],
+    mut i: usize,
+) -> Result<(PatchHunk<String>, usize), ParseError> {
+    let (a_start, mut a_left, b_start, mut b_left) =
+        parse_ranges(lines[i])
+            .ok_or_else(|| ParseError::new(i, "invalid hunk heading"))?;
+    let mut hunk = PatchHunk { a_start, b_start, lines: vec![] };
+    i += 1;
+    while a_left > 0 || b_left > 0 {
+        let line = match lines.get(i) {
+            Some(line) => *line,
+            None => return Err(ParseError::new(i, "incomplete hunk")),
+        };
+        let text = line.get(1..).unwrap_or_default().to_string();
+        match line.chars().next() {
+            // Some tools strip the space from empty context lines.
+            Some('\n') | Some('\r') if a_left > 0 && b_left > 0 => {
+                hunk.lines.push(PatchLine::Context(line.to_string()));
+                a_left -= 1;
+                b_left -= 1;
+            }
+            Some(' ') if a_left > 0 && b_left > 0 => {
+                hunk.lines.push(PatchLine::Context(text));
+                a_left -= 1;
+                b_left -= 1;
+            }
+            Some('-') if a_left > 0 => {
+                hunk.lines.push(PatchLine::Delete(text));
+                a_left -= 1;
+            }
+            Some('+') if b_left > 0 => {
+                hunk.lines.push(PatchLine::Insert(text));
+                b_left -= 1;
+            }
+            Some('\\') => strip_newline(&mut hunk, i)?,
+            _ => return Err(ParseError::new(i, "invalid hunk line")),
+        }
+        i += 1;
+    }
+    if matches!(lines.get(i), Some(line) if line.starts_with('\\')) {
+        strip_newline(&mut hunk, i)?;
+        i += 1;
+    }
+    Ok((hunk, i))
+}
+
+// Handles a "\ No newline at end of file" line by removing the line
+// ending from the preceding line.
+fn strip_newline(
+    hunk: &mut PatchHunk<String>,
+    i: usize,
+) -> Result<(), ParseError> {
+    match hunk.lines.last_mut() {
+        Some(PatchLine::Context(text))
+        | Some(PatchLine::Delete(text))
+        | Some(PatchLine::Insert(text)) => {
+            if text.ends_with('\n') {
+                text.pop();
+                if text.ends_with('\r') {
+                    text.pop();
+                }
+            }
+            Ok(())
+        }
+        None => {
+            Err(ParseError::new(i, "no newline marker without a line"))
+        }
+    }
+}
+
+// Returns the a start, a length, b start, and b length from a hunk's
+// "@@ -l,s +l,s @@" heading.
+fn parse_ranges(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let mut parts = line.strip_prefix("@@ -")?.splitn(2, " @@");
+    let (a_range, b_range) = parts.next()?.split_once(" +")?;
+    parts.next()?;
+    let (a_start, a_len) = parse_range(a_range)?;
+    let (b_start, b_len) = parse_range(b_range)?;
+    Some((a_start, a_len, b_start, b_len))
+}
+
+// Returns the 0-based start and the length of a "l,s" or "l" range.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (start, len) = match range.split_once(',') {
+        Some((start, len)) => (start.parse().ok()?, len.parse().ok()?),
+        None => (range.parse().ok()?, 1),
+    };
+    match (start, len) {
+        (start, 0) => Some((start, 0)),
+        (0, _) => None,
+        (start, len) => Some((start - 1, len)),
+    }
+}
+
+/// The reason why a unified diff could not be parsed by
+/// [`parse_unified_diff()`](fn.parse_unified_diff.html), and the
+/// (1-based) number of the line where the problem was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(i: usize, message: &str) -> Self {
+        ParseError { line: i + 1, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
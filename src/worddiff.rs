@@ -0,0 +1,143 @@
+// Copyright © 2019 Qtrac Ltd. All rights reserved.
+
+use crate::differ::Differ;
+use crate::intraline::words;
+use crate::structs::{Span, Tag, WordDiffStyle};
+
+const RED: &str = "\x1B[31m";
+const GREEN: &str = "\x1B[32m";
+const RESET: &str = "\x1B[m";
+
+/// Returns the spans that convert text `a` into text `b` word by word.
+///
+/// The texts are split into words (runs of alphanumeric characters or
+/// underscores), runs of whitespace, single punctuation characters, and
+/// newlines, and these are compared. The spans' indexes are byte offsets
+/// into the texts, so can be used to slice them, and cover both texts
+/// completely.
+///
+/// ```
+/// use differ::{word_spans, Span};
+///
+/// let spans = word_spans("the quick fox", "the slow fox");
+/// assert_eq!(spans[1], Span::replace(4, 9, 4, 8));
+/// ```
+pub fn word_spans(a: &str, b: &str) -> Vec<Span> {
+    let (a_offsets, a_tokens) = tokenize(a);
+    let (b_offsets, b_tokens) = tokenize(b);
+    // Whitespace tokens are so common that autojunk would make them
+    // unmatchable.
+    let differ = Differ::new(&a_tokens, &b_tokens).autojunk(false);
+    differ
+        .spans()
+        .iter()
+        .map(|span| Span {
+            tag: span.tag.clone(),
+            a_start: a_offsets[span.a_start],
+            a_end: a_offsets[span.a_end],
+            b_start: b_offsets[span.b_start],
+            b_end: b_offsets[span.b_end],
+        })
+        .collect()
+}
+
+/// Returns a word diff of text `a` and text `b` marked up in the given
+/// `style`; this is the equivalent of `git diff --word-diff`.
+///
+/// The words are found by [`word_spans()`](fn.word_spans.html). The
+/// common text is output unchanged, so all the original whitespace is
+/// preserved. In the `Plain` and `Colour` styles a change that spans
+/// lines is marked up line by line so that every line's markup is
+/// complete.
+///
+/// ```
+/// use differ::{word_diff, WordDiffStyle};
+///
+/// let a = "The quick brown fox.\n";
+/// let b = "The quick red fox!\n";
+/// assert_eq!(
+///     word_diff(a, b, WordDiffStyle::Plain),
+///     "The quick [-brown-]{+red+} fox[-.-]{+!+}\n"
+/// );
+/// assert_eq!(
+///     word_diff(a, b, WordDiffStyle::Porcelain),
+///     " The quick \n-brown\n+red\n  fox\n-.\n+!\n~\n"
+/// );
+/// ```
+pub fn word_diff(a: &str, b: &str, style: WordDiffStyle) -> String {
+    let mut diff = String::new();
+    for span in word_spans(a, b) {
+        let a_text = &a[span.a_start..span.a_end];
+        let b_text = &b[span.b_start..span.b_end];
+        if span.tag == Tag::Equal {
+            push_text(&mut diff, style, a_text, ' ');
+        } else {
+            push_text(&mut diff, style, a_text, '-');
+            push_text(&mut diff, style, b_text, '+');
+        }
+    }
+    diff
+}
+
+// Pushes common (' '), deleted ('-'), or inserted ('+') text, line by
+// line, marked up in the given style.
+fn push_text(
+    diff: &mut String,
+    style: WordDiffStyle,
+    text: &str,
+    tag: char,
+) {
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            diff.push_str(match style {
+                WordDiffStyle::Porcelain => "~\n",
+                _ => "\n",
+            });
+        }
+        if line.is_empty() {
+            continue;
+        }
+        match (style, tag) {
+            (WordDiffStyle::Porcelain, _) => {
+                diff.push(tag);
+                diff.push_str(line);
+                diff.push('\n');
+            }
+            (_, ' ') => diff.push_str(line),
+            (WordDiffStyle::Plain, '-') => {
+                diff.push_str(&format!("[-{}-]", line))
+            }
+            (WordDiffStyle::Plain, _) => {
+                diff.push_str(&format!("{{+{}+}}", line))
+            }
+            (WordDiffStyle::Colour, _) => {
+                let colour = if tag == '-' { RED } else { GREEN };
+                diff.push_str(&format!("{}{}{}", colour, line, RESET));
+            }
+        }
+    }
+}
+
+// Returns the byte offset of each token (plus a final offset of the
+// text's length) and the tokens themselves; each newline is a token in
+// its own right.
+fn tokenize(text: &str) -> (Vec<usize>, Vec<&str>) {
+    let mut tokens = vec![];
+    for line in text.split_inclusive('\n') {
+        match line.strip_suffix('\n') {
+            Some(content) => {
+                tokens.extend(words(content));
+                tokens.push("\n");
+            }
+            None => tokens.extend(words(line)),
+        }
+    }
+    let mut offsets = Vec::with_capacity(tokens.len() + 1);
+    let mut offset = 0;
+    for token in &tokens {
+        offsets.push(offset);
+        offset += token.len();
+    }
+    offsets.push(offset);
+    (offsets, tokens)
+}